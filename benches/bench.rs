@@ -102,10 +102,27 @@ fn plane(c: &mut Criterion) {
         )
     });
 
+    let mut p8b_1080p = Plane::<u8>::new(1920, 1080, 0, 0, 0, 0);
+    c.bench_function("plane fill 8-bit", |b| {
+        b.iter(|| p8b_1080p.fill(black_box(42)))
+    });
+
+    let mut p10b_1080p = Plane::<u16>::new(1920, 1080, 0, 0, 0, 0);
+    c.bench_function("plane fill 10-bit", |b| {
+        b.iter(|| p10b_1080p.fill(black_box(512)))
+    });
+
+    let mut p8b_1080p_padded = Plane::<u8>::new(1920, 1080, 0, 0, 32, 32);
+    c.bench_function("plane fill 8-bit padded", |b| {
+        b.iter(|| p8b_1080p_padded.fill(black_box(42)))
+    });
+
     c.bench_function("plane downsampled", |b| {
         b.iter(|| p8b.downsampled(black_box(320), black_box(240)))
     });
 
+    c.bench_function("plane downsample_2x", |b| b.iter(|| p8b.downsample_2x()));
+
     c.bench_function("plane downscale", |b| b.iter(|| p8b.downscale::<2>()));
 
     // This may seem silly to benchmark, but there is some math in the iterator
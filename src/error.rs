@@ -0,0 +1,289 @@
+// Copyright (c) 2024, The rav1e contributors. All rights reserved
+//
+// This source code is subject to the terms of the BSD 2 Clause License and
+// the Alliance for Open Media Patent License 1.0. If the BSD 2 Clause License
+// was not distributed with this source code in the LICENSE file, you can
+// obtain it at www.aomedia.org/license/software. If the Alliance for Open
+// Media Patent License 1.0 was not distributed with this source code in the
+// PATENTS file, you can obtain it at www.aomedia.org/license/patent.
+
+use core::fmt;
+
+/// Errors returned by fallible plane and frame operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The visible dimensions of two planes do not match.
+    DimensionMismatch {
+        /// Width and height of the plane the operation was called on.
+        expected: (usize, usize),
+        /// Width and height of the other plane.
+        found: (usize, usize),
+    },
+    /// The size of a plane overflows or exceeds the maximum allocation size.
+    AllocationTooLarge {
+        /// Requested width.
+        width: usize,
+        /// Requested height.
+        height: usize,
+    },
+    /// A buffer does not have the length required by the operation.
+    DataLength {
+        /// Length required by the operation, in elements of the buffer.
+        expected: usize,
+        /// Length of the buffer.
+        found: usize,
+    },
+    /// A channel index is not below the number of interleaved channels.
+    InvalidChannel {
+        /// Requested channel.
+        channel: usize,
+        /// Number of interleaved channels.
+        num_channels: usize,
+    },
+    /// A chroma sampling could not be parsed or converted, or does not match
+    /// the planes it is used with.
+    InvalidChromaSampling,
+    /// The planes of a frame are inconsistent with each other.
+    InconsistentFrame,
+    /// A plane configuration is inconsistent.
+    InvalidPlaneConfig,
+    /// A plane has padding or alignment slack around its visible pixels.
+    Padded,
+    /// A bit depth is zero, does not fit in the pixel type, or is too small
+    /// for the pixels it is used with.
+    UnsupportedBitDepth {
+        /// Requested bit depth.
+        bit_depth: usize,
+    },
+    /// A stride is smaller than the row it is used for.
+    InvalidStride {
+        /// Stride in elements of the buffer.
+        stride: usize,
+        /// Length of a row in elements of the buffer.
+        row_len: usize,
+    },
+    /// A rectangle extends past the visible area of a plane.
+    OutOfBounds {
+        /// Horizontal position of the rectangle.
+        x: usize,
+        /// Vertical position of the rectangle.
+        y: usize,
+        /// Width of the rectangle.
+        width: usize,
+        /// Height of the rectangle.
+        height: usize,
+        /// Width of the plane.
+        plane_width: usize,
+        /// Height of the plane.
+        plane_height: usize,
+    },
+}
+
+/// Broad category of an [`Error`].
+///
+/// New kinds may be added in future releases, so matching on it requires a
+/// wildcard arm, unlike matching on the variants of [`Error`] directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Dimensions or coordinates do not match the plane or frame.
+    Dimensions,
+    /// A buffer is too short or laid out incorrectly.
+    Buffer,
+    /// A pixel format parameter such as the bit depth is not supported.
+    Format,
+    /// The planes or plane configurations are inconsistent.
+    Layout,
+    /// A plane or frame is too large to allocate.
+    Allocation,
+}
+
+impl Error {
+    /// Returns the category of the error.
+    pub const fn kind(&self) -> ErrorKind {
+        match self {
+            Error::DimensionMismatch { .. } | Error::OutOfBounds { .. } => ErrorKind::Dimensions,
+            Error::DataLength { .. } | Error::InvalidStride { .. } => ErrorKind::Buffer,
+            Error::InvalidChannel { .. }
+            | Error::InvalidChromaSampling
+            | Error::UnsupportedBitDepth { .. } => ErrorKind::Format,
+            Error::InconsistentFrame | Error::InvalidPlaneConfig | Error::Padded => {
+                ErrorKind::Layout
+            }
+            Error::AllocationTooLarge { .. } => ErrorKind::Allocation,
+        }
+    }
+
+    /// Returns `true` if the error was caused by the arguments or data given
+    /// to the operation, and can be fixed by the caller.
+    pub const fn is_input_error(&self) -> bool {
+        !self.is_resource_error()
+    }
+
+    /// Returns `true` if the error was caused by exceeding the resources
+    /// available to the operation, such as the maximum allocation size.
+    pub const fn is_resource_error(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Allocation)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::DimensionMismatch { expected, found } => write!(
+                f,
+                "plane dimensions do not match: expected {}x{}, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            Error::AllocationTooLarge { width, height } => {
+                write!(
+                    f,
+                    "plane of size {}x{} is too large to allocate",
+                    width, height
+                )
+            }
+            Error::DataLength { expected, found } => write!(
+                f,
+                "invalid data length: expected {}, found {}",
+                expected, found
+            ),
+            Error::InvalidChannel {
+                channel,
+                num_channels,
+            } => write!(
+                f,
+                "channel {} is out of range for {} channels",
+                channel, num_channels
+            ),
+            Error::InvalidChromaSampling => write!(f, "invalid chroma sampling"),
+            Error::InconsistentFrame => write!(f, "inconsistent frame planes"),
+            Error::InvalidPlaneConfig => write!(f, "invalid plane configuration"),
+            Error::Padded => write!(f, "plane has padding"),
+            Error::UnsupportedBitDepth { bit_depth } => {
+                write!(f, "unsupported bit depth: {}", bit_depth)
+            }
+            Error::InvalidStride { stride, row_len } => write!(
+                f,
+                "stride {} is smaller than the row length {}",
+                stride, row_len
+            ),
+            Error::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+                plane_width,
+                plane_height,
+            } => write!(
+                f,
+                "rectangle {}x{} at ({}, {}) is out of bounds of plane of size {}x{}",
+                width, height, x, y, plane_width, plane_height
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    use wasm_bindgen_test::*;
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn display() {
+        let tests = [
+            (
+                Error::DimensionMismatch {
+                    expected: (64, 32),
+                    found: (32, 64),
+                },
+                "plane dimensions do not match: expected 64x32, found 32x64",
+            ),
+            (
+                Error::AllocationTooLarge {
+                    width: 100000,
+                    height: 100000,
+                },
+                "plane of size 100000x100000 is too large to allocate",
+            ),
+            (
+                Error::DataLength {
+                    expected: 12,
+                    found: 10,
+                },
+                "invalid data length: expected 12, found 10",
+            ),
+            (
+                Error::InvalidChannel {
+                    channel: 3,
+                    num_channels: 3,
+                },
+                "channel 3 is out of range for 3 channels",
+            ),
+            (Error::InvalidChromaSampling, "invalid chroma sampling"),
+            (Error::InconsistentFrame, "inconsistent frame planes"),
+            (Error::InvalidPlaneConfig, "invalid plane configuration"),
+            (Error::Padded, "plane has padding"),
+            (
+                Error::UnsupportedBitDepth { bit_depth: 12 },
+                "unsupported bit depth: 12",
+            ),
+            (
+                Error::InvalidStride {
+                    stride: 60,
+                    row_len: 64,
+                },
+                "stride 60 is smaller than the row length 64",
+            ),
+            (
+                Error::OutOfBounds {
+                    x: 10,
+                    y: 20,
+                    width: 8,
+                    height: 4,
+                    plane_width: 16,
+                    plane_height: 16,
+                },
+                "rectangle 8x4 at (10, 20) is out of bounds of plane of size 16x16",
+            ),
+        ];
+
+        for (error, expected) in tests {
+            assert_eq!(error.to_string(), expected);
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn kind() {
+        let error = Error::AllocationTooLarge {
+            width: 100000,
+            height: 100000,
+        };
+        assert_eq!(error.kind(), ErrorKind::Allocation);
+        assert!(error.is_resource_error());
+        assert!(!error.is_input_error());
+
+        let error = Error::InvalidStride {
+            stride: 60,
+            row_len: 64,
+        };
+        assert_eq!(error.kind(), ErrorKind::Buffer);
+        assert!(error.is_input_error());
+        assert!(!error.is_resource_error());
+
+        assert_eq!(Error::Padded.kind(), ErrorKind::Layout);
+        assert_eq!(
+            Error::UnsupportedBitDepth { bit_depth: 0 }.kind(),
+            ErrorKind::Format
+        );
+    }
+}
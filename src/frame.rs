@@ -7,61 +7,2936 @@
 // Media Patent License 1.0 was not distributed with this source code in the
 // PATENTS file, you can obtain it at www.aomedia.org/license/patent.
 
-use crate::math::*;
+use crate::error::Error;
 use crate::pixel::*;
 use crate::plane::*;
 
+use core::mem::size_of;
+use core::num::NonZeroUsize;
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 
+/// Order in which the planes of a frame are stored in planar data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaneOrder {
+    /// Y, then U (Cb), then V (Cr).
+    #[default]
+    Yuv,
+    /// Y, then V (Cr), then U (Cb).
+    Yvu,
+}
+
+#[cfg(feature = "std")]
+impl PlaneOrder {
+    /// Returns the indices of the planes in this order.
+    const fn indices(self) -> [usize; 3] {
+        match self {
+            PlaneOrder::Yuv => [0, 1, 2],
+            PlaneOrder::Yvu => [0, 2, 1],
+        }
+    }
+}
+
+/// Synthetic image generated by [`Frame::test_pattern`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestPattern {
+    /// Mid-gray luma and neutral chroma.
+    SolidGray,
+    /// Luma ramping from black on the left to white on the right.
+    HorizontalGradient,
+    /// Luma ramping from black at the top to white at the bottom.
+    VerticalGradient,
+    /// Alternating black and white squares.
+    Checkerboard {
+        /// Side of the squares in luma pixels.
+        size: usize,
+    },
+    /// Eight vertical 75% color bars, with BT.709 limited range values.
+    ColorBars,
+}
+
+impl TestPattern {
+    /// 8-bit Y, U and V values of the color bars: white, yellow, cyan, green,
+    /// magenta, red, blue and black.
+    const COLOR_BARS: [[u32; 3]; 8] = [
+        [180, 128, 128],
+        [168, 44, 136],
+        [145, 147, 44],
+        [133, 63, 52],
+        [63, 193, 204],
+        [51, 109, 212],
+        [28, 212, 120],
+        [16, 128, 128],
+    ];
+}
+
+/// Matrix coefficients for converting between limited range YUV and RGB, as
+/// used by [`Frame::to_rgb8`] and [`Frame::from_rgb8`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMatrix {
+    /// ITU-R BT.601, for standard definition content.
+    Bt601,
+    /// ITU-R BT.709, for high definition content.
+    #[default]
+    Bt709,
+    /// ITU-R BT.2020 non-constant luminance, for ultra high definition content.
+    Bt2020,
+}
+
+#[cfg(feature = "std")]
+impl ColorMatrix {
+    /// Returns the weights of red and blue in luma, `Kr` and `Kb`.
+    const fn coefficients(self) -> (f32, f32) {
+        match self {
+            ColorMatrix::Bt601 => (0.299, 0.114),
+            ColorMatrix::Bt709 => (0.2126, 0.0722),
+            ColorMatrix::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+/// Format of a frame without its pixels, as returned by [`Frame::info`].
+///
+/// This is cheap to copy and compare, e.g. for keying buffer pools on the
+/// format of the frames they hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FrameInfo {
+    /// Width of the luma plane.
+    pub width: usize,
+    /// Height of the luma plane.
+    pub height: usize,
+    /// Chroma sampling of the frame.
+    pub chroma_sampling: ChromaSampling,
+    /// Position of the subsampled chroma samples.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub chroma_sample_position: ChromaSamplePosition,
+    /// Bit depth of the samples.
+    pub bit_depth: usize,
+}
+
 /// Represents a raw video frame
-#[derive(Debug, Clone, Eq, PartialEq)]
+///
+/// The frame does not record the bit depth of its samples. It has to be
+/// tracked alongside the frame and passed to the methods that depend on it,
+/// such as [`Frame::convert_subsampling`]. The chroma sampling is implied by
+/// the planes and returned by [`Frame::chroma_sampling`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Frame<T: Pixel> {
     /// Planes constituting the frame.
     pub planes: [Plane<T>; 3],
+    /// Optional alpha plane, with the same dimensions as the luma plane.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub alpha: Option<Plane<T>>,
 }
 
 impl<T: Pixel> Frame<T> {
     /// Creates a new frame with the given parameters.
     ///
     /// Allocates data for the planes.
+    ///
+    /// # Panics
+    ///
+    /// - If the size of the frame overflows, see [`Frame::try_new_with_padding`]
     pub fn new_with_padding(
         width: usize,
         height: usize,
         chroma_sampling: ChromaSampling,
         luma_padding: usize,
     ) -> Self {
-        let luma_width = width.align_power_of_two(3);
-        let luma_height = height.align_power_of_two(3);
+        Self::try_new_with_padding(width, height, chroma_sampling, luma_padding)
+            .expect("frame dimensions overflow")
+    }
+
+    /// Same as [`Frame::new_with_padding`], but checks that the size of the
+    /// planes can be computed and allocated before allocating them.
+    ///
+    /// # Errors
+    ///
+    /// - If the size of any plane overflows or exceeds `isize::MAX` bytes
+    pub fn try_new_with_padding(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+    ) -> Result<Self, Error> {
+        let [luma_cfg, chroma_cfg] =
+            Self::plane_configs(width, height, chroma_sampling, luma_padding, None)?;
+
+        Ok(Self::with_configs(luma_cfg, chroma_cfg))
+    }
+
+    /// Runs the checks of [`Frame::try_new_with_padding`] without allocating
+    /// the frame, for rejecting invalid parameters before committing to a
+    /// possibly large allocation.
+    ///
+    /// # Errors
+    ///
+    /// - If the size of any plane overflows or exceeds `isize::MAX` bytes
+    pub fn check_new_with_padding(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+    ) -> Result<(), Error> {
+        Self::plane_configs(width, height, chroma_sampling, luma_padding, None).map(|_| ())
+    }
+
+    /// Same as [`Frame::new_with_padding`], but with the horizontal and
+    /// vertical padding of the chroma planes given explicitly instead of
+    /// derived from the luma padding and the chroma decimation.
+    ///
+    /// # Panics
+    ///
+    /// - If the size of the frame overflows, see [`Frame::try_new_with_padding`]
+    pub fn new_with_chroma_padding(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        chroma_xpad: usize,
+        chroma_ypad: usize,
+    ) -> Self {
+        let [luma_cfg, chroma_cfg] = Self::plane_configs(
+            width,
+            height,
+            chroma_sampling,
+            luma_padding,
+            Some((chroma_xpad, chroma_ypad)),
+        )
+        .expect("frame dimensions overflow");
+
+        Self::with_configs(luma_cfg, chroma_cfg)
+    }
+
+    /// Allocates a frame with the given luma and chroma plane configurations.
+    fn with_configs(luma_cfg: PlaneConfig, chroma_cfg: PlaneConfig) -> Self {
+        Frame {
+            planes: [
+                Plane::with_config(luma_cfg),
+                Plane::with_config(chroma_cfg.clone()),
+                Plane::with_config(chroma_cfg),
+            ],
+            alpha: None,
+        }
+    }
+
+    /// Creates a new frame with the given parameters, with all pixels of
+    /// each plane, including the padding, set to the corresponding entry of
+    /// `values`.
+    ///
+    /// The planes are filled as they are allocated, so this is cheaper than
+    /// filling a frame from [`Frame::new_with_padding`]. The chroma values are
+    /// ignored for monochrome frames.
+    ///
+    /// # Panics
+    ///
+    /// - If the size of the frame overflows, see [`Frame::try_new_with_padding`]
+    pub fn new_filled(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        values: [T; 3],
+    ) -> Self {
+        let [luma_cfg, chroma_cfg] =
+            Self::plane_configs(width, height, chroma_sampling, luma_padding, None)
+                .expect("frame dimensions overflow");
+
+        Frame {
+            planes: [
+                Plane::with_config_filled(luma_cfg, values[0]),
+                Plane::with_config_filled(chroma_cfg.clone(), values[1]),
+                Plane::with_config_filled(chroma_cfg, values[2]),
+            ],
+            alpha: None,
+        }
+    }
+
+    /// Creates a new frame with the given parameters, with each visible pixel
+    /// set to the value returned by `f` for the index of its plane and its
+    /// coordinates within that plane.
+    ///
+    /// The planes are generated in order, each one in row-major order.
+    pub fn new_from_fn(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        mut f: impl FnMut(usize, usize, usize) -> T,
+    ) -> Self {
+        let mut frame = Self::new_with_padding(width, height, chroma_sampling, luma_padding);
+        for (index, plane) in frame.planes.iter_mut().enumerate() {
+            plane.fill_with(|x, y| f(index, x, y));
+        }
+
+        frame
+    }
+
+    /// Creates a new frame with the given parameters, filled with a synthetic
+    /// image with values scaled to `bit_depth`.
+    ///
+    /// Patterns other than [`TestPattern::ColorBars`] have neutral chroma.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in `T`
+    ///
+    /// # Panics
+    ///
+    /// - If the size of a [`TestPattern::Checkerboard`] is zero
+    pub fn test_pattern(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        bit_depth: usize,
+        pattern: TestPattern,
+    ) -> Result<Self, Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
+        }
+
+        let (xdec, ydec) = chroma_sampling.get_decimation().unwrap_or((0, 0));
+        let max = u32::cast_from(T::max_for_depth(bit_depth));
+        let neutral = u32::cast_from(T::neutral_for_depth(bit_depth));
+        let scale = |value: u32| {
+            let value = if bit_depth >= 8 {
+                value << (bit_depth - 8)
+            } else {
+                value >> (8 - bit_depth)
+            };
+            value.min(max)
+        };
+        let ramp = |pos: usize, len: usize| {
+            (pos.min(len.saturating_sub(1)) as u64 * u64::from(max)
+                / len.saturating_sub(1).max(1) as u64) as u32
+        };
+
+        Ok(Self::new_from_fn(
+            width,
+            height,
+            chroma_sampling,
+            luma_padding,
+            |index, x, y| {
+                // Position of the sample in the luma plane
+                let (x, y) = if index == 0 {
+                    (x, y)
+                } else {
+                    (x << xdec, y << ydec)
+                };
+
+                let value = match pattern {
+                    TestPattern::ColorBars => {
+                        let bar = (x * 8 / width.max(1)).min(7);
+                        scale(TestPattern::COLOR_BARS[bar][index])
+                    }
+                    _ if index != 0 => neutral,
+                    TestPattern::SolidGray => neutral,
+                    TestPattern::HorizontalGradient => ramp(x, width),
+                    TestPattern::VerticalGradient => ramp(y, height),
+                    TestPattern::Checkerboard { size } => {
+                        if (x / size + y / size) % 2 == 0 {
+                            max
+                        } else {
+                            0
+                        }
+                    }
+                };
+
+                T::cast_from(value)
+            },
+        ))
+    }
+
+    /// Reconfigures the frame with the given parameters, as if it had been
+    /// created with [`Frame::new_with_padding`].
+    ///
+    /// The allocation of each plane is reused, keeping its contents, when it
+    /// has the size required by the new parameters. This avoids allocating
+    /// and clearing the buffers for every frame when they are pooled.
+    ///
+    /// A reused plane holds the pixels of the previous frame, so the caller
+    /// must write every visible pixel before reading it. The buffers are
+    /// always initialized, so reading early yields stale pixels rather than
+    /// undefined behavior.
+    ///
+    /// # Panics
+    ///
+    /// - If the size of the frame overflows, see [`Frame::try_new_with_padding`]
+    pub fn reinit(
+        &mut self,
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+    ) {
+        let [luma_cfg, chroma_cfg] =
+            Self::plane_configs(width, height, chroma_sampling, luma_padding, None)
+                .expect("frame dimensions overflow");
+
+        self.planes[0].reinit_with_config(luma_cfg);
+        self.planes[1].reinit_with_config(chroma_cfg.clone());
+        self.planes[2].reinit_with_config(chroma_cfg);
+        if let Some(alpha) = &mut self.alpha {
+            alpha.reinit_with_config(self.planes[0].cfg.clone());
+        }
+    }
+
+    /// Returns the configurations of the luma and chroma planes of a frame.
+    ///
+    /// The chroma padding is derived from the luma padding unless given.
+    fn plane_configs(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        chroma_padding: Option<(usize, usize)>,
+    ) -> Result<[PlaneConfig; 2], Error> {
+        let too_large = Error::AllocationTooLarge { width, height };
+        let align = |v: usize| v.checked_add(7).map(|v| v & !7).ok_or(too_large);
+        let luma_width = align(width)?;
+        let luma_height = align(height)?;
 
         let (chroma_decimation_x, chroma_decimation_y) =
             chroma_sampling.get_decimation().unwrap_or((0, 0));
         let (chroma_width, chroma_height) =
             chroma_sampling.get_chroma_dimensions(luma_width, luma_height);
-        let chroma_padding_x = luma_padding >> chroma_decimation_x;
-        let chroma_padding_y = luma_padding >> chroma_decimation_y;
+        let (chroma_padding_x, chroma_padding_y) = chroma_padding.unwrap_or((
+            luma_padding >> chroma_decimation_x,
+            luma_padding >> chroma_decimation_y,
+        ));
+
+        Ok([
+            PlaneConfig::try_new(
+                luma_width,
+                luma_height,
+                0,
+                0,
+                luma_padding,
+                luma_padding,
+                size_of::<T>(),
+            )?,
+            PlaneConfig::try_new(
+                chroma_width,
+                chroma_height,
+                chroma_decimation_x,
+                chroma_decimation_y,
+                chroma_padding_x,
+                chroma_padding_y,
+                size_of::<T>(),
+            )?,
+        ])
+    }
+
+    /// Returns the PSNR in dB of each plane of `other` against this frame.
+    ///
+    /// The peak value is derived from `bit_depth`. Planes without any visible
+    /// pixels, such as the chroma planes of a monochrome frame, report `NaN`,
+    /// while identical planes report infinity.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in `T`
+    /// - If any plane of `other` differs in dimensions from this frame
+    #[cfg(feature = "std")]
+    pub fn psnr(&self, other: &Frame<T>, bit_depth: usize) -> Result<[f64; 3], Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
+        }
+
+        let max = ((1u64 << bit_depth) - 1) as f64;
+        let mut psnr = [0.0; 3];
+
+        for (out, (a, b)) in psnr
+            .iter_mut()
+            .zip(self.planes.iter().zip(other.planes.iter()))
+        {
+            let sse = a.sse(b)?;
+            let len = (a.cfg.width * a.cfg.height) as f64;
+
+            *out = if a.cfg.width == 0 || a.cfg.height == 0 {
+                f64::NAN
+            } else if sse == 0 {
+                f64::INFINITY
+            } else {
+                10.0 * (max * max * len / sse as f64).log10()
+            };
+        }
+
+        Ok(psnr)
+    }
+
+    /// Returns the frame rotated by 90 degrees clockwise (not padded).
+    ///
+    /// The chroma decimation of the planes is swapped along with their
+    /// dimensions, so 4:2:0, 4:4:4 and monochrome frames keep their
+    /// subsampling.
+    ///
+    /// # Errors
+    ///
+    /// - If the frame is 4:2:2, since the rotated chroma planes would be
+    ///   subsampled vertically only, which [`ChromaSampling`] cannot describe
+    pub fn rotate_90_cw(&self) -> Result<Frame<T>, Error> {
+        self.check_rotatable()?;
+
+        Ok(Frame {
+            planes: [
+                self.planes[0].rotate_90_cw(),
+                self.planes[1].rotate_90_cw(),
+                self.planes[2].rotate_90_cw(),
+            ],
+            alpha: self.alpha.as_ref().map(Plane::rotate_90_cw),
+        })
+    }
+
+    /// Returns the frame rotated by 90 degrees counter-clockwise (not padded).
+    ///
+    /// The chroma decimation of the planes is swapped along with their
+    /// dimensions, so 4:2:0, 4:4:4 and monochrome frames keep their
+    /// subsampling.
+    ///
+    /// # Errors
+    ///
+    /// - If the frame is 4:2:2, since the rotated chroma planes would be
+    ///   subsampled vertically only, which [`ChromaSampling`] cannot describe
+    pub fn rotate_90_ccw(&self) -> Result<Frame<T>, Error> {
+        self.check_rotatable()?;
+
+        Ok(Frame {
+            planes: [
+                self.planes[0].rotate_90_ccw(),
+                self.planes[1].rotate_90_ccw(),
+                self.planes[2].rotate_90_ccw(),
+            ],
+            alpha: self.alpha.as_ref().map(Plane::rotate_90_ccw),
+        })
+    }
+
+    /// Checks that the chroma planes are decimated equally in both directions,
+    /// so that rotating them keeps a valid chroma sampling.
+    fn check_rotatable(&self) -> Result<(), Error> {
+        let chroma = &self.planes[1].cfg;
+        if chroma.width != 0 && chroma.height != 0 && chroma.xdec != chroma.ydec {
+            return Err(Error::InvalidChromaSampling);
+        }
+
+        Ok(())
+    }
+
+    /// Mirrors all the planes of the frame horizontally, in place.
+    pub fn flip_horizontal(&mut self) {
+        for plane in self.planes.iter_mut().chain(&mut self.alpha) {
+            plane.flip_horizontal();
+        }
+    }
+
+    /// Mirrors all the planes of the frame vertically, in place.
+    pub fn flip_vertical(&mut self) {
+        for plane in self.planes.iter_mut().chain(&mut self.alpha) {
+            plane.flip_vertical();
+        }
+    }
+
+    /// Returns a copy of the frame with its chroma planes resampled to
+    /// `chroma_sampling`. The luma plane is copied unchanged.
+    ///
+    /// Chroma samples are replicated when upsampling and averaged when
+    /// downsampling. Converting to monochrome drops the chroma data, while
+    /// converting from monochrome fills the chroma planes with the neutral
+    /// value for `bit_depth`.
+    pub fn convert_subsampling(&self, chroma_sampling: ChromaSampling, bit_depth: usize) -> Self {
+        let luma = &self.planes[0];
+        let PlaneConfig {
+            width,
+            height,
+            xpad,
+            ypad,
+            ..
+        } = luma.cfg;
+
+        let (xdec, ydec) = match chroma_sampling.get_decimation() {
+            Some(decimation) => decimation,
+            None => {
+                return Frame {
+                    planes: [
+                        luma.clone(),
+                        Self::empty_chroma_plane(luma),
+                        Self::empty_chroma_plane(luma),
+                    ],
+                    alpha: self.alpha.clone(),
+                };
+            }
+        };
+        let (chroma_width, chroma_height) = chroma_sampling.get_chroma_dimensions(width, height);
+        let neutral = T::neutral_for_depth(bit_depth);
+
+        let resample = |src: &Plane<T>| {
+            let mut plane = Plane::new(
+                chroma_width,
+                chroma_height,
+                xdec,
+                ydec,
+                xpad >> xdec,
+                ypad >> ydec,
+            );
+
+            for (y, row) in plane.rows_iter_mut().enumerate() {
+                for (x, dst) in row.iter_mut().enumerate() {
+                    if src.cfg.width == 0 || src.cfg.height == 0 {
+                        *dst = neutral;
+                        continue;
+                    }
+
+                    // Source samples covering the luma area of this sample
+                    let src_x0 = (x << xdec) >> src.cfg.xdec;
+                    let src_y0 = (y << ydec) >> src.cfg.ydec;
+                    let src_x1 =
+                        ((((x + 1) << xdec).min(width) - 1) >> src.cfg.xdec).min(src.cfg.width - 1);
+                    let src_y1 = ((((y + 1) << ydec).min(height) - 1) >> src.cfg.ydec)
+                        .min(src.cfg.height - 1);
+
+                    let mut sum = 0;
+                    for src_y in src_y0..=src_y1 {
+                        for src_x in src_x0..=src_x1 {
+                            sum += u32::cast_from(src.p(src_x, src_y));
+                        }
+                    }
+                    let count = ((src_x1 - src_x0 + 1) * (src_y1 - src_y0 + 1)) as u32;
+                    *dst = T::cast_from((sum + count / 2) / count);
+                }
+            }
+
+            plane.pad(width, height);
+            plane
+        };
 
         Frame {
             planes: [
-                Plane::new(luma_width, luma_height, 0, 0, luma_padding, luma_padding),
-                Plane::new(
-                    chroma_width,
-                    chroma_height,
-                    chroma_decimation_x,
-                    chroma_decimation_y,
-                    chroma_padding_x,
-                    chroma_padding_y,
-                ),
-                Plane::new(
-                    chroma_width,
-                    chroma_height,
-                    chroma_decimation_x,
-                    chroma_decimation_y,
-                    chroma_padding_x,
-                    chroma_padding_y,
-                ),
+                luma.clone(),
+                resample(&self.planes[1]),
+                resample(&self.planes[2]),
             ],
+            alpha: self.alpha.clone(),
+        }
+    }
+
+    /// Returns a chroma plane without any pixels, as found in monochrome
+    /// frames.
+    fn empty_chroma_plane(luma: &Plane<T>) -> Plane<T> {
+        Plane::new(0, 0, 0, 0, luma.cfg.xpad, luma.cfg.ypad)
+    }
+
+    /// Adds an alpha plane to the frame, replacing any existing one. The
+    /// alpha plane has the configuration of the luma plane and is fully
+    /// opaque for `bit_depth`.
+    pub fn with_alpha(mut self, bit_depth: usize) -> Self {
+        let mut alpha = Plane::with_config(self.planes[0].cfg.clone());
+        alpha.fill(T::max_for_depth(bit_depth));
+        self.alpha = Some(alpha);
+        self
+    }
+
+    /// Returns the alpha plane of the frame, if any.
+    pub fn alpha(&self) -> Option<&Plane<T>> {
+        self.alpha.as_ref()
+    }
+
+    /// Returns the alpha plane of the frame mutably, if any.
+    pub fn alpha_mut(&mut self) -> Option<&mut Plane<T>> {
+        self.alpha.as_mut()
+    }
+
+    /// Returns the luma plane of the frame.
+    pub fn luma(&self) -> &Plane<T> {
+        &self.planes[0]
+    }
+
+    /// Returns the luma plane of the frame, mutably.
+    pub fn luma_mut(&mut self) -> &mut Plane<T> {
+        &mut self.planes[0]
+    }
+
+    /// Converts the frame into a monochrome frame, keeping the luma and
+    /// alpha planes and dropping the chroma planes.
+    pub fn into_luma(self) -> Frame<T> {
+        let [luma, _, _] = self.planes;
+        let empty = Self::empty_chroma_plane(&luma);
+
+        Frame {
+            planes: [luma, empty.clone(), empty],
+            alpha: self.alpha,
+        }
+    }
+
+    /// Writes the visible pixels of the planes to `w`, one plane after the
+    /// other in the given order. Samples wider than a byte are written as
+    /// little-endian.
+    ///
+    /// Only the luma plane is written for monochrome frames.
+    ///
+    /// # Errors
+    ///
+    /// - If writing to `w` fails
+    #[cfg(feature = "std")]
+    pub fn write_planar<W: Write>(&self, w: &mut W, order: PlaneOrder) -> io::Result<()> {
+        let mut buf = Vec::new();
+
+        for index in order.indices() {
+            let plane = &self.planes[index];
+            for y in 0..plane.cfg.height {
+                plane.row_bytes_into(y, &mut buf);
+                w.write_all(&buf)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the visible pixels of the planes from `r`, one plane after the
+    /// other in the given order. Samples wider than a byte are read as
+    /// little-endian.
+    ///
+    /// Only the luma plane is read for monochrome frames.
+    ///
+    /// # Errors
+    ///
+    /// - If reading from `r` fails, including if it ends before all planes are read
+    #[cfg(feature = "std")]
+    pub fn read_planar<R: Read>(&mut self, r: &mut R, order: PlaneOrder) -> io::Result<()> {
+        let bytewidth = size_of::<T>();
+        let mut buf = Vec::new();
+
+        for index in order.indices() {
+            let plane = &mut self.planes[index];
+            buf.resize(plane.cfg.width * bytewidth, 0);
+
+            for row in plane.rows_iter_mut() {
+                r.read_exact(&mut buf)?;
+                for (pixel, bytes) in row.iter_mut().zip(buf.chunks_exact(bytewidth)) {
+                    let mut sample = [0; 2];
+                    sample[..bytewidth].copy_from_slice(bytes);
+                    *pixel = T::cast_from(u16::from_le_bytes(sample));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the largest absolute difference between the visible pixels of
+    /// two frames, including their alpha planes, or `None` if only one of
+    /// them has an alpha plane or the dimensions of any of their planes
+    /// differ.
+    pub fn max_abs_diff(&self, other: &Frame<T>) -> Option<u16> {
+        if self.alpha.is_some() != other.alpha.is_some() {
+            return None;
+        }
+
+        self.planes
+            .iter()
+            .chain(&self.alpha)
+            .zip(other.planes.iter().chain(&other.alpha))
+            .map(|(a, b)| a.max_abs_diff(b))
+            .try_fold(0, |max, diff| diff.map(|diff| max.max(diff)))
+    }
+
+    /// Returns whether the visible pixels of two frames, including their alpha
+    /// planes, differ by at most `tolerance`. Frames with different dimensions
+    /// or with an alpha plane on one side only are never equal.
+    pub fn approx_eq(&self, other: &Frame<T>, tolerance: u16) -> bool {
+        self.max_abs_diff(other)
+            .map_or(false, |diff| diff <= tolerance)
+    }
+
+    /// Exchanges the contents of two frames, without copying any pixel.
+    pub fn swap_with(&mut self, other: &mut Frame<T>) {
+        core::mem::swap(self, other);
+    }
+
+    /// Exchanges the U and V planes of the frame, without copying any pixel.
+    ///
+    /// This is useful to fix up sources with reversed Cb and Cr planes.
+    pub fn swap_uv(&mut self) {
+        self.planes.swap(1, 2);
+    }
+
+    /// Replaces each visible pixel of every plane, including the alpha plane,
+    /// with the value returned by `f` for it. The padding is left untouched.
+    pub fn map_in_place(&mut self, mut f: impl FnMut(T) -> T) {
+        for plane in self.planes.iter_mut().chain(&mut self.alpha) {
+            plane.map_in_place(&mut f);
+        }
+    }
+
+    /// Returns the chroma sampling of the frame, as implied by the decimation
+    /// of its chroma planes.
+    ///
+    /// # Errors
+    ///
+    /// - If the decimation of the chroma planes does not correspond to any
+    ///   [`ChromaSampling`], e.g. for chroma subsampled vertically only
+    pub const fn chroma_sampling(&self) -> Result<ChromaSampling, Error> {
+        let chroma = &self.planes[1].cfg;
+        if chroma.width == 0 || chroma.height == 0 {
+            return Ok(ChromaSampling::Cs400);
+        }
+
+        match (chroma.xdec, chroma.ydec) {
+            (0, 0) => Ok(ChromaSampling::Cs444),
+            (1, 0) => Ok(ChromaSampling::Cs422),
+            (1, 1) => Ok(ChromaSampling::Cs420),
+            _ => Err(Error::InvalidChromaSampling),
+        }
+    }
+
+    /// Returns the width and height of the luma plane.
+    pub const fn luma_dimensions(&self) -> (usize, usize) {
+        (self.planes[0].cfg.width, self.planes[0].cfg.height)
+    }
+
+    /// Returns the width and height of the chroma planes, or `None` for
+    /// monochrome frames.
+    pub const fn chroma_dimensions(&self) -> Option<(usize, usize)> {
+        let chroma = &self.planes[1].cfg;
+        if chroma.width == 0 || chroma.height == 0 {
+            return None;
         }
+
+        Some((chroma.width, chroma.height))
+    }
+
+    /// Splits the frame into its luma plane, its chroma planes and its chroma
+    /// sampling.
+    ///
+    /// The chroma planes are `None` for monochrome frames. The alpha plane,
+    /// if any, is dropped.
+    ///
+    /// # Errors
+    ///
+    /// - If the chroma sampling of the frame cannot be determined, see
+    ///   [`Frame::chroma_sampling`]
+    #[allow(clippy::type_complexity)]
+    pub fn into_parts(
+        self,
+    ) -> Result<(Plane<T>, Option<Plane<T>>, Option<Plane<T>>, ChromaSampling), Error> {
+        let chroma_sampling = self.chroma_sampling()?;
+        let [y, u, v] = self.planes;
+
+        Ok(if chroma_sampling == ChromaSampling::Cs400 {
+            (y, None, None, chroma_sampling)
+        } else {
+            (y, Some(u), Some(v), chroma_sampling)
+        })
+    }
+
+    /// Reassembles a frame from planes previously obtained from
+    /// [`Frame::into_parts`], or built separately.
+    ///
+    /// # Errors
+    ///
+    /// - If the presence of the chroma planes or their decimation does not
+    ///   match `chroma_sampling`
+    /// - If the dimensions of a chroma plane do not match those implied by the
+    ///   luma plane and `chroma_sampling`
+    pub fn from_parts(
+        y: Plane<T>,
+        u: Option<Plane<T>>,
+        v: Option<Plane<T>>,
+        chroma_sampling: ChromaSampling,
+    ) -> Result<Self, Error> {
+        let (u, v) = match (chroma_sampling.get_decimation(), u, v) {
+            (None, None, None) => {
+                let empty = Self::empty_chroma_plane(&y);
+                (empty.clone(), empty)
+            }
+            (Some((xdec, ydec)), Some(u), Some(v)) => {
+                let expected = chroma_sampling.get_chroma_dimensions(y.cfg.width, y.cfg.height);
+                for plane in [&u, &v] {
+                    if (plane.cfg.xdec, plane.cfg.ydec) != (xdec, ydec) {
+                        return Err(Error::InvalidChromaSampling);
+                    }
+                    if (plane.cfg.width, plane.cfg.height) != expected {
+                        return Err(Error::DimensionMismatch {
+                            expected,
+                            found: (plane.cfg.width, plane.cfg.height),
+                        });
+                    }
+                }
+                (u, v)
+            }
+            _ => return Err(Error::InvalidChromaSampling),
+        };
+
+        Ok(Frame {
+            planes: [y, u, v],
+            alpha: None,
+        })
+    }
+
+    /// Returns the size in bytes of the visible pixels of all planes, which is
+    /// the size of the frame in planar formats such as y4m.
+    ///
+    /// The alpha plane is included when present.
+    pub fn visible_byte_len(&self) -> usize {
+        self.planes
+            .iter()
+            .chain(&self.alpha)
+            .map(Plane::visible_byte_len)
+            .sum()
+    }
+
+    /// Returns the size in bytes of the buffers of all planes, including the
+    /// padding.
+    ///
+    /// The alpha plane, when present, and the padding buffers allocated for
+    /// the chroma planes of monochrome frames are included.
+    pub fn allocated_byte_len(&self) -> usize {
+        self.planes
+            .iter()
+            .chain(&self.alpha)
+            .map(Plane::allocated_byte_len)
+            .sum()
+    }
+
+    /// Splits an interlaced frame into its top and bottom fields (not padded).
+    ///
+    /// Each plane is split along its own rows, so the chroma planes of 4:2:0
+    /// frames are fielded at chroma resolution.
+    ///
+    /// # Panics
+    ///
+    /// - If the height of any plane is odd
+    pub fn separate_fields(&self) -> (Frame<T>, Frame<T>) {
+        let [(y_top, y_bottom), (u_top, u_bottom), (v_top, v_bottom)] =
+            [&self.planes[0], &self.planes[1], &self.planes[2]].map(Plane::separate_fields);
+        let (alpha_top, alpha_bottom) = match self.alpha.as_ref().map(Plane::separate_fields) {
+            Some((top, bottom)) => (Some(top), Some(bottom)),
+            None => (None, None),
+        };
+
+        (
+            Frame {
+                planes: [y_top, u_top, v_top],
+                alpha: alpha_top,
+            },
+            Frame {
+                planes: [y_bottom, u_bottom, v_bottom],
+                alpha: alpha_bottom,
+            },
+        )
+    }
+
+    /// Interleaves a top and a bottom field into a frame (not padded), undoing
+    /// [`Frame::separate_fields`].
+    ///
+    /// The alpha plane is only kept if both fields have one.
+    ///
+    /// # Errors
+    ///
+    /// - If any plane of the fields differs in dimensions
+    pub fn weave_fields(top: &Frame<T>, bottom: &Frame<T>) -> Result<Frame<T>, Error> {
+        let alpha = match (&top.alpha, &bottom.alpha) {
+            (Some(top), Some(bottom)) => Some(Plane::weave_fields(top, bottom)?),
+            _ => None,
+        };
+
+        Ok(Frame {
+            planes: [
+                Plane::weave_fields(&top.planes[0], &bottom.planes[0])?,
+                Plane::weave_fields(&top.planes[1], &bottom.planes[1])?,
+                Plane::weave_fields(&top.planes[2], &bottom.planes[2])?,
+            ],
+            alpha,
+        })
+    }
+
+    /// Rescales the visible pixels of every plane, including the alpha plane,
+    /// from `from_bit_depth` to `to_bit_depth`.
+    ///
+    /// See [`Plane::rescale_bit_depth`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - If either bit depth is zero or does not fit in `T`, in which case the
+    ///   frame is left unmodified
+    pub fn rescale_bit_depth(
+        &mut self,
+        from_bit_depth: usize,
+        to_bit_depth: usize,
+    ) -> Result<(), Error> {
+        for plane in self.planes.iter_mut().chain(&mut self.alpha) {
+            plane.rescale_bit_depth(from_bit_depth, to_bit_depth)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns a frame of another pixel type with the same geometry, with
+    /// each visible pixel of every plane, including the alpha plane, set to
+    /// the value returned by `f` for the corresponding pixel (not padded).
+    ///
+    /// This allows arbitrary conversions between bit depths, such as tone
+    /// mapping high bit depth content to 8 bits.
+    pub fn convert_with<U: Pixel>(&self, mut f: impl FnMut(T) -> U) -> Frame<U> {
+        Frame {
+            planes: [
+                self.planes[0].convert_with(&mut f),
+                self.planes[1].convert_with(&mut f),
+                self.planes[2].convert_with(&mut f),
+            ],
+            alpha: self.alpha.as_ref().map(|alpha| alpha.convert_with(&mut f)),
+        }
+    }
+
+    /// Checks that the planes of the frame are consistent with each other, as
+    /// they are when created by the constructors of this type.
+    ///
+    /// This is a cheap sanity check for frames assembled or modified by hand.
+    ///
+    /// # Errors
+    ///
+    /// - If the configuration of a plane is invalid, or its buffer is too
+    ///   short for it
+    /// - If the luma plane is decimated, the chroma planes differ from each
+    ///   other, their dimensions or decimation do not match those implied by
+    ///   the luma plane, or the alpha plane differs in dimensions from the
+    ///   luma plane
+    pub fn validate(&self) -> Result<(), Error> {
+        for plane in self.planes.iter().chain(&self.alpha) {
+            plane.cfg.validate()?;
+            if plane.data.len() < plane.cfg.stride * plane.cfg.alloc_height {
+                return Err(Error::InvalidPlaneConfig);
+            }
+        }
+
+        let luma = &self.planes[0].cfg;
+        let u = &self.planes[1].cfg;
+        let v = &self.planes[2].cfg;
+        let chroma_sampling = self
+            .chroma_sampling()
+            .map_err(|_| Error::InconsistentFrame)?;
+
+        let consistent = luma.xdec == 0
+            && luma.ydec == 0
+            && (u.width, u.height, u.xdec, u.ydec) == (v.width, v.height, v.xdec, v.ydec)
+            && (u.width, u.height)
+                == chroma_sampling.get_chroma_dimensions(luma.width, luma.height)
+            && chroma_sampling
+                .get_decimation()
+                .map_or(true, |decimation| decimation == (u.xdec, u.ydec))
+            && self.alpha.as_ref().map_or(true, |alpha| {
+                (alpha.cfg.width, alpha.cfg.height) == (luma.width, luma.height)
+            });
+
+        if !consistent {
+            return Err(Error::InconsistentFrame);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the value returned by `f` for each visible pixel of every plane,
+    /// including the alpha plane, into the corresponding pixel of `dst`, like
+    /// [`Frame::map_in_place`] without modifying this frame.
+    ///
+    /// # Errors
+    ///
+    /// - If only one of the frames has an alpha plane, or any plane of `dst`
+    ///   differs in dimensions from this frame, in which case `dst` is left
+    ///   unmodified
+    pub fn apply_into(&self, dst: &mut Frame<T>, mut f: impl FnMut(T) -> T) -> Result<(), Error> {
+        if self.alpha.is_some() != dst.alpha.is_some() {
+            return Err(Error::InconsistentFrame);
+        }
+        for (src, dst) in self
+            .planes
+            .iter()
+            .chain(&self.alpha)
+            .zip(dst.planes.iter().chain(&dst.alpha))
+        {
+            if (src.cfg.width, src.cfg.height) != (dst.cfg.width, dst.cfg.height) {
+                return Err(Error::DimensionMismatch {
+                    expected: (src.cfg.width, src.cfg.height),
+                    found: (dst.cfg.width, dst.cfg.height),
+                });
+            }
+        }
+
+        let dst_planes = dst.planes.iter_mut().chain(&mut dst.alpha);
+        for (src, dst) in self.planes.iter().chain(&self.alpha).zip(dst_planes) {
+            src.apply_into(dst, &mut f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the pixels of the `width` by `height` rectangle at `(x, y)` of the
+    /// luma plane to the first entry of `values`, and the chroma samples
+    /// covering it to the other entries.
+    ///
+    /// The chroma rectangle is scaled by the chroma decimation, rounding
+    /// outwards.
+    ///
+    /// # Errors
+    ///
+    /// - If the rectangle extends past the visible area of the luma plane
+    pub fn fill_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        values: [T; 3],
+    ) -> Result<(), Error> {
+        self.planes[0].fill_rect(x, y, width, height, values[0])?;
+
+        for (plane, value) in self.planes[1..].iter_mut().zip(&values[1..]) {
+            if plane.cfg.width == 0 || plane.cfg.height == 0 {
+                continue;
+            }
+
+            let (xdec, ydec) = (plane.cfg.xdec, plane.cfg.ydec);
+            let chroma_x = x >> xdec;
+            let chroma_y = y >> ydec;
+            let chroma_width = ((x + width + xdec) >> xdec) - chroma_x;
+            let chroma_height = ((y + height + ydec) >> ydec) - chroma_y;
+            plane.fill_rect(chroma_x, chroma_y, chroma_width, chroma_height, *value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the largest visible pixel of all planes, including the alpha
+    /// plane, or `None` if the frame has no visible pixels.
+    pub fn max_pixel_value(&self) -> Option<T> {
+        self.planes
+            .iter()
+            .chain(&self.alpha)
+            .filter_map(Plane::max)
+            .max()
+    }
+
+    /// Returns whether all the visible pixels of the frame fit in
+    /// `bit_depth` bits.
+    ///
+    /// This detects values carried over from content of a higher bit depth,
+    /// which [`Plane::clamp_to_depth`] can then fix.
+    pub fn fits_bit_depth(&self, bit_depth: usize) -> bool {
+        self.max_pixel_value()
+            .map_or(true, |max| max <= T::max_for_depth(bit_depth))
+    }
+
+    /// Creates a new frame with the given parameters from separate 8-bit or
+    /// little-endian 16-bit sources for each plane, as returned by most
+    /// decoders, and pads it.
+    ///
+    /// Each source is a buffer and its stride in bytes, and must hold at least
+    /// `stride * height` bytes for the height of its plane. The samples are
+    /// one byte wide if `bit_depth` is at most 8, and two bytes wide otherwise.
+    /// Chroma sources must be given if and only if `chroma_sampling` is not
+    /// [`ChromaSampling::Cs400`].
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in `T`
+    /// - If the chroma sources do not match `chroma_sampling`
+    /// - If a stride is smaller than a row of its plane
+    /// - If a source is too short for its plane
+    /// - If the size of a source or of the frame overflows, see
+    ///   [`Frame::try_new_with_padding`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_strided_planes(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        bit_depth: usize,
+        y: (&[u8], NonZeroUsize),
+        u: Option<(&[u8], NonZeroUsize)>,
+        v: Option<(&[u8], NonZeroUsize)>,
+    ) -> Result<Self, Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
+        }
+        let sources = match (chroma_sampling, u, v) {
+            (ChromaSampling::Cs400, None, None) => [Some(y), None, None],
+            (ChromaSampling::Cs400, _, _) | (_, None, _) | (_, _, None) => {
+                return Err(Error::InvalidChromaSampling)
+            }
+            (_, u, v) => [Some(y), u, v],
+        };
+        let bytewidth = if bit_depth > 8 { 2 } else { 1 };
+        let (chroma_width, chroma_height) = chroma_sampling.get_chroma_dimensions(width, height);
+
+        for (index, source) in sources.iter().enumerate() {
+            if let Some((data, stride)) = source {
+                let (w, h) = if index == 0 {
+                    (width, height)
+                } else {
+                    (chroma_width, chroma_height)
+                };
+                let row_len = w
+                    .checked_mul(bytewidth)
+                    .ok_or(Error::AllocationTooLarge { width, height })?;
+                if stride.get() < row_len {
+                    return Err(Error::InvalidStride {
+                        stride: stride.get(),
+                        row_len,
+                    });
+                }
+                let expected = stride
+                    .get()
+                    .checked_mul(h)
+                    .ok_or(Error::AllocationTooLarge { width, height })?;
+                if data.len() < expected {
+                    return Err(Error::DataLength {
+                        expected,
+                        found: data.len(),
+                    });
+                }
+            }
+        }
+
+        let mut frame = Self::try_new_with_padding(width, height, chroma_sampling, luma_padding)?;
+        for (plane, source) in frame.planes.iter_mut().zip(sources) {
+            if let Some((data, stride)) = source {
+                plane.copy_from_raw_u8(data, stride.get(), bytewidth);
+                plane.pad(width, height);
+            }
+        }
+
+        Ok(frame)
+    }
+
+    /// Returns a frame with half the resolution of this one, averaging each
+    /// 2x2 block of every plane with rounding to nearest. The planes are not
+    /// padded.
+    ///
+    /// The last column and row of planes with odd dimensions are dropped.
+    ///
+    /// # Errors
+    ///
+    /// - If the chroma sampling of the frame cannot be determined, see
+    ///   [`Frame::chroma_sampling`]
+    /// - If the halved chroma planes would not match the halved luma plane
+    ///   for the chroma sampling of the frame
+    pub fn downsample_2x(&self) -> Result<Self, Error> {
+        let chroma_sampling = self.chroma_sampling()?;
+        let luma = &self.planes[0].cfg;
+        let chroma = &self.planes[1].cfg;
+        if chroma_sampling != ChromaSampling::Cs400
+            && chroma_sampling.get_chroma_dimensions(luma.width / 2, luma.height / 2)
+                != (chroma.width / 2, chroma.height / 2)
+        {
+            return Err(Error::InconsistentFrame);
+        }
+
+        // Unlike `Plane::downsample_2x`, keep the decimation of each plane
+        // relative to the frame
+        let halve = |plane: &Plane<T>| {
+            let mut new = plane.downsample_2x();
+            new.cfg.xdec = plane.cfg.xdec;
+            new.cfg.ydec = plane.cfg.ydec;
+            new
+        };
+
+        Ok(Frame {
+            planes: [
+                halve(&self.planes[0]),
+                halve(&self.planes[1]),
+                halve(&self.planes[2]),
+            ],
+            alpha: self.alpha.as_ref().map(halve),
+        })
+    }
+
+    /// Returns up to `levels` successively halved frames, starting with
+    /// [`Frame::downsample_2x`] of this one.
+    ///
+    /// Fewer levels are returned if the luma plane would become empty or the
+    /// chroma planes could no longer be halved consistently.
+    pub fn pyramid(&self, levels: usize) -> Vec<Self> {
+        let mut pyramid: Vec<Self> = Vec::with_capacity(levels);
+        while pyramid.len() < levels {
+            let prev = pyramid.last().unwrap_or(self);
+            let luma = &prev.planes[0].cfg;
+            if luma.width < 2 || luma.height < 2 {
+                break;
+            }
+            match prev.downsample_2x() {
+                Ok(frame) => pyramid.push(frame),
+                Err(_) => break,
+            }
+        }
+
+        pyramid
+    }
+
+    /// Converts the top-left `width` by `height` pixels of the frame to
+    /// interleaved 8-bit RGB, assuming limited range samples with the given
+    /// bit depth.
+    ///
+    /// The dimensions are given explicitly since the luma plane of frames
+    /// created by this crate is rounded up to a multiple of 8. Passing those
+    /// given to [`Frame::from_rgb8`] restores its input without the added
+    /// columns and rows.
+    ///
+    /// Chroma is upsampled by repeating the nearest sample. Monochrome frames
+    /// convert to gray.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in `T`
+    /// - If `width` or `height` exceed the dimensions of the luma plane
+    #[cfg(feature = "std")]
+    pub fn to_rgb8(
+        &self,
+        width: usize,
+        height: usize,
+        bit_depth: usize,
+        matrix: ColorMatrix,
+    ) -> Result<Vec<u8>, Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
+        }
+        let (plane_width, plane_height) = self.luma_dimensions();
+        if width > plane_width || height > plane_height {
+            return Err(Error::OutOfBounds {
+                x: 0,
+                y: 0,
+                width,
+                height,
+                plane_width,
+                plane_height,
+            });
+        }
+
+        let (kr, kb) = matrix.coefficients();
+        let kg = 1.0 - kr - kb;
+        let scale = (1u64 << bit_depth) as f32 / 256.0;
+        let neutral = 128.0 * scale;
+        let to_u8 = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u8;
+
+        let [luma, u, v] = &self.planes;
+        let has_chroma = self.chroma_dimensions().is_some();
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        for (y, row) in luma.rows_iter().take(height).enumerate() {
+            for (x, &pixel) in row[..width].iter().enumerate() {
+                let (cb, cr) = if has_chroma {
+                    let cx = (x >> u.cfg.xdec).min(u.cfg.width - 1);
+                    let cy = (y >> u.cfg.ydec).min(u.cfg.height - 1);
+                    (
+                        u32::cast_from(u.p(cx, cy)) as f32,
+                        u32::cast_from(v.p(cx, cy)) as f32,
+                    )
+                } else {
+                    (neutral, neutral)
+                };
+
+                let luma = (u32::cast_from(pixel) as f32 / scale - 16.0) / 219.0;
+                let cb = (cb - neutral) / (224.0 * scale);
+                let cr = (cr - neutral) / (224.0 * scale);
+                let r = (2.0 * (1.0 - kr)).mul_add(cr, luma);
+                let b = (2.0 * (1.0 - kb)).mul_add(cb, luma);
+                let g = kb.mul_add(-b, kr.mul_add(-r, luma)) / kg;
+                rgb.extend_from_slice(&[to_u8(r), to_u8(g), to_u8(b)]);
+            }
+        }
+
+        Ok(rgb)
+    }
+
+    /// Creates a new frame with the given parameters from interleaved 8-bit
+    /// RGB, converted to limited range samples with the given bit depth and
+    /// padded.
+    ///
+    /// Each chroma sample is converted from the average color of the pixels
+    /// it covers.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in `T`
+    /// - If the length of `rgb` is not `width * height * 3`
+    /// - If the size of the frame overflows, see [`Frame::try_new_with_padding`]
+    #[cfg(feature = "std")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_rgb8(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        bit_depth: usize,
+        rgb: &[u8],
+        matrix: ColorMatrix,
+    ) -> Result<Self, Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
+        }
+        let expected = width
+            .checked_mul(height)
+            .and_then(|len| len.checked_mul(3))
+            .ok_or(Error::AllocationTooLarge { width, height })?;
+        if rgb.len() != expected {
+            return Err(Error::DataLength {
+                expected,
+                found: rgb.len(),
+            });
+        }
+
+        let (kr, kb) = matrix.coefficients();
+        let kg = 1.0 - kr - kb;
+        let scale = (1u64 << bit_depth) as f32 / 256.0;
+        let max = u32::cast_from(T::max_for_depth(bit_depth)) as f32;
+        let to_pixel = |value: f32| T::cast_from(value.round().clamp(0.0, max) as u32);
+        let color = |x: usize, y: usize| {
+            let i = (y * width + x) * 3;
+            [rgb[i], rgb[i + 1], rgb[i + 2]].map(|c| f32::from(c) / 255.0)
+        };
+
+        let mut frame = Self::try_new_with_padding(width, height, chroma_sampling, luma_padding)?;
+        for y in 0..height {
+            for x in 0..width {
+                let [r, g, b] = color(x, y);
+                let luma = kb.mul_add(b, kr.mul_add(r, kg * g));
+                frame.planes[0][(x, y)] = to_pixel(219.0f32.mul_add(luma, 16.0) * scale);
+            }
+        }
+        frame.planes[0].pad(width, height);
+
+        if chroma_sampling != ChromaSampling::Cs400 {
+            let (xdec, ydec) = chroma_sampling.get_decimation().unwrap_or((0, 0));
+            let (chroma_width, chroma_height) =
+                chroma_sampling.get_chroma_dimensions(width, height);
+            for cy in 0..chroma_height {
+                for cx in 0..chroma_width {
+                    let xs = (cx << xdec)..((cx + 1) << xdec).min(width);
+                    let ys = (cy << ydec)..((cy + 1) << ydec).min(height);
+                    let count = (xs.len() * ys.len()) as f32;
+                    let mut sum = [0.0; 3];
+                    for y in ys {
+                        for x in xs.clone() {
+                            for (sum, c) in sum.iter_mut().zip(color(x, y)) {
+                                *sum += c;
+                            }
+                        }
+                    }
+
+                    let [r, g, b] = sum.map(|c| c / count);
+                    let luma = kb.mul_add(b, kr.mul_add(r, kg * g));
+                    let cb = (b - luma) / (2.0 * (1.0 - kb));
+                    let cr = (r - luma) / (2.0 * (1.0 - kr));
+                    frame.planes[1][(cx, cy)] = to_pixel(224.0f32.mul_add(cb, 128.0) * scale);
+                    frame.planes[2][(cx, cy)] = to_pixel(224.0f32.mul_add(cr, 128.0) * scale);
+                }
+            }
+            frame.planes[1].pad(width, height);
+            frame.planes[2].pad(width, height);
+        }
+
+        Ok(frame)
+    }
+
+    /// Returns `true` if each plane of the frame is uniform, see
+    /// [`Plane::is_uniform`].
+    ///
+    /// The empty chroma planes of monochrome frames are ignored.
+    pub fn is_flat(&self) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.cfg.width * plane.cfg.height == 0 || plane.is_uniform().is_some())
+    }
+
+    /// Returns the mean absolute difference between the luma planes of this
+    /// frame and `prev`, normalized by the maximum value for `bit_depth`.
+    ///
+    /// The score ranges from 0 for identical luma to 1 for black against
+    /// white, and can be thresholded to detect scene cuts. If `downsample` is
+    /// `true`, both luma planes are halved with [`Plane::downsample_2x`]
+    /// first, which is faster and less sensitive to noise. Frames with an
+    /// empty luma plane score 0.
+    ///
+    /// # Errors
+    ///
+    /// - If the luma planes do not have the same width and height
+    pub fn scene_change_score(
+        &self,
+        prev: &Frame<T>,
+        bit_depth: usize,
+        downsample: bool,
+    ) -> Result<f64, Error> {
+        let (cur, prev) = (&self.planes[0], &prev.planes[0]);
+        let (sad, len) = if downsample {
+            cur.check_dimensions(prev)?;
+            let cur = cur.downsample_2x();
+            (
+                cur.sad(&prev.downsample_2x())?,
+                cur.cfg.width * cur.cfg.height,
+            )
+        } else {
+            (cur.sad(prev)?, cur.cfg.width * cur.cfg.height)
+        };
+        if len == 0 {
+            return Ok(0.0);
+        }
+
+        let max = u32::cast_from(T::max_for_depth(bit_depth));
+        Ok(sad as f64 / (len as f64 * f64::from(max)))
+    }
+
+    /// Returns the format of the frame, with the given bit depth and chroma
+    /// sample position since the frame does not record them.
+    ///
+    /// # Errors
+    ///
+    /// - If the chroma sampling of the frame cannot be determined, see
+    ///   [`Frame::chroma_sampling`]
+    pub const fn info(
+        &self,
+        bit_depth: usize,
+        chroma_sample_position: ChromaSamplePosition,
+    ) -> Result<FrameInfo, Error> {
+        let (width, height) = self.luma_dimensions();
+        let chroma_sampling = match self.chroma_sampling() {
+            Ok(chroma_sampling) => chroma_sampling,
+            Err(error) => return Err(error),
+        };
+
+        Ok(FrameInfo {
+            width,
+            height,
+            chroma_sampling,
+            chroma_sample_position,
+            bit_depth,
+        })
+    }
+
+    /// Returns the memory layout of each plane of the frame, for diagnosing
+    /// padding and alignment issues.
+    ///
+    /// The layouts are in plane order, followed by that of the alpha plane if
+    /// the frame has one.
+    pub fn layout_report(&self) -> Vec<PlaneLayout> {
+        self.planes
+            .iter()
+            .chain(&self.alpha)
+            .map(Plane::layout)
+            .collect()
+    }
+
+    /// Checks that the frame has the format described by `info`.
+    ///
+    /// The frame does not record its bit depth, so only that the bit depth of
+    /// `info` fits in `T` is checked, not the values of the pixels. Neither
+    /// does it record its chroma sample position, which is not checked.
+    ///
+    /// # Errors
+    ///
+    /// - If the luma dimensions differ from those of `info`
+    /// - If the chroma sampling differs from that of `info`
+    /// - If the bit depth of `info` is zero or does not fit in `T`
+    pub fn expect_info(&self, info: &FrameInfo) -> Result<(), Error> {
+        let dimensions = self.luma_dimensions();
+        if dimensions != (info.width, info.height) {
+            return Err(Error::DimensionMismatch {
+                expected: (info.width, info.height),
+                found: dimensions,
+            });
+        }
+        if self.chroma_sampling()? != info.chroma_sampling {
+            return Err(Error::InvalidChromaSampling);
+        }
+        if info.bit_depth == 0 || info.bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth {
+                bit_depth: info.bit_depth,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the frame has the format described by `info`, see
+    /// [`Frame::expect_info`].
+    pub fn matches_info(&self, info: &FrameInfo) -> bool {
+        self.expect_info(info).is_ok()
+    }
+
+    /// Iterates over the luma samples of the frame in row-major order, each
+    /// with its coordinates and the co-located chroma samples, as
+    /// `(x, y, y_value, u_value, v_value)`.
+    ///
+    /// Subsampled chroma samples are repeated for every luma sample they
+    /// cover. Monochrome frames yield the neutral value for `bit_depth` as
+    /// chroma.
+    pub fn yuv_samples(
+        &self,
+        bit_depth: usize,
+    ) -> impl Iterator<Item = (usize, usize, T, T, T)> + '_ {
+        let [luma, u, v] = &self.planes;
+        let neutral = T::neutral_for_depth(bit_depth);
+        let has_chroma = self.chroma_dimensions().is_some();
+
+        luma.rows_iter().enumerate().flat_map(move |(y, row)| {
+            let cy = (y >> u.cfg.ydec).min(u.cfg.height.saturating_sub(1));
+            row.iter().enumerate().map(move |(x, &luma)| {
+                if has_chroma {
+                    let cx = (x >> u.cfg.xdec).min(u.cfg.width - 1);
+                    (x, y, luma, u.p(cx, cy), v.p(cx, cy))
+                } else {
+                    (x, y, luma, neutral, neutral)
+                }
+            })
+        })
+    }
+
+    /// Copies the visible pixels of every plane of `src`, including the alpha
+    /// plane, into this frame, which may have a different padding.
+    ///
+    /// This allows reusing a preallocated output frame.
+    ///
+    /// # Errors
+    ///
+    /// - If `src` has a different chroma sampling, only one of the frames has
+    ///   an alpha plane, or any plane of `src` differs in dimensions from this
+    ///   frame, in which case this frame is left unmodified
+    pub fn copy_from(&mut self, src: &Frame<T>) -> Result<(), Error> {
+        if self.chroma_sampling()? != src.chroma_sampling()? {
+            return Err(Error::InvalidChromaSampling);
+        }
+        if self.alpha.is_some() != src.alpha.is_some() {
+            return Err(Error::InconsistentFrame);
+        }
+        let src_planes = || src.planes.iter().chain(&src.alpha);
+        for (dst, src) in self.planes.iter().chain(&self.alpha).zip(src_planes()) {
+            dst.check_dimensions(src)?;
+        }
+
+        for (dst, src) in self
+            .planes
+            .iter_mut()
+            .chain(&mut self.alpha)
+            .zip(src_planes())
+        {
+            dst.copy_from(src)?;
+        }
+
+        Ok(())
+    }
+
+    /// Relabels the chroma sampling of the frame without reallocating, by
+    /// changing the decimation of the chroma planes.
+    ///
+    /// This corrects frames whose chroma planes were built with the wrong
+    /// decimation, and only succeeds if their dimensions already match
+    /// `chroma_sampling`.
+    ///
+    /// # Errors
+    ///
+    /// - If the dimensions of the chroma planes are not those of
+    ///   `chroma_sampling` for the luma plane, in which case the frame is left
+    ///   unmodified
+    pub fn set_subsampling(&mut self, chroma_sampling: ChromaSampling) -> Result<(), Error> {
+        let (width, height) = self.luma_dimensions();
+        let expected = chroma_sampling.get_chroma_dimensions(width, height);
+        for plane in &self.planes[1..] {
+            let found = (plane.cfg.width, plane.cfg.height);
+            if found != expected {
+                return Err(Error::DimensionMismatch { expected, found });
+            }
+        }
+
+        self.set_subsampling_unchecked(chroma_sampling);
+
+        Ok(())
+    }
+
+    /// Same as [`Frame::set_subsampling`], but without checking the
+    /// dimensions of the chroma planes.
+    ///
+    /// This can produce an inconsistent frame, on which padding and other
+    /// operations may panic. Use [`Frame::validate`] to check the result.
+    pub fn set_subsampling_unchecked(&mut self, chroma_sampling: ChromaSampling) {
+        let (xdec, ydec) = chroma_sampling.get_decimation().unwrap_or((0, 0));
+        for plane in &mut self.planes[1..] {
+            plane.cfg.xdec = xdec;
+            plane.cfg.ydec = ydec;
+        }
+    }
+}
+
+/// A frame whose pixel type is chosen at runtime from its bit depth.
+///
+/// This allows handling sources whose bit depth is only known once they are
+/// opened without dispatching over the pixel type for every operation.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum DynFrame {
+    /// A frame with a bit depth of at most 8.
+    U8 {
+        /// The frame itself.
+        frame: Frame<u8>,
+        /// Bit depth of the pixels of the frame.
+        bit_depth: usize,
+    },
+    /// A frame with a bit depth above 8.
+    U16 {
+        /// The frame itself.
+        frame: Frame<u16>,
+        /// Bit depth of the pixels of the frame.
+        bit_depth: usize,
+    },
+}
+
+impl DynFrame {
+    /// Creates a new frame with the given parameters, using `u8` pixels for
+    /// bit depths of at most 8 and `u16` pixels otherwise.
+    ///
+    /// Allocates data for the planes.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is not within `1..=16`
+    pub fn new_with_padding(
+        width: usize,
+        height: usize,
+        chroma_sampling: ChromaSampling,
+        luma_padding: usize,
+        bit_depth: usize,
+    ) -> Result<Self, Error> {
+        match bit_depth {
+            1..=8 => Ok(DynFrame::U8 {
+                frame: Frame::new_with_padding(width, height, chroma_sampling, luma_padding),
+                bit_depth,
+            }),
+            9..=16 => Ok(DynFrame::U16 {
+                frame: Frame::new_with_padding(width, height, chroma_sampling, luma_padding),
+                bit_depth,
+            }),
+            _ => Err(Error::UnsupportedBitDepth { bit_depth }),
+        }
+    }
+
+    /// Wraps an existing 8-bit frame whose pixels have `bit_depth` bits.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is not within `1..=8`
+    pub fn from_u8(frame: Frame<u8>, bit_depth: usize) -> Result<Self, Error> {
+        match bit_depth {
+            1..=8 => Ok(DynFrame::U8 { frame, bit_depth }),
+            _ => Err(Error::UnsupportedBitDepth { bit_depth }),
+        }
+    }
+
+    /// Wraps an existing 16-bit frame whose pixels have `bit_depth` bits.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is not within `9..=16`
+    pub fn from_u16(frame: Frame<u16>, bit_depth: usize) -> Result<Self, Error> {
+        match bit_depth {
+            9..=16 => Ok(DynFrame::U16 { frame, bit_depth }),
+            _ => Err(Error::UnsupportedBitDepth { bit_depth }),
+        }
+    }
+
+    /// Returns the bit depth of the pixels of the frame.
+    pub const fn bit_depth(&self) -> usize {
+        match self {
+            DynFrame::U8 { bit_depth, .. } | DynFrame::U16 { bit_depth, .. } => *bit_depth,
+        }
+    }
+
+    /// Returns the width of the luma plane.
+    pub fn width(&self) -> usize {
+        match self {
+            DynFrame::U8 { frame, .. } => frame.planes[0].cfg.width,
+            DynFrame::U16 { frame, .. } => frame.planes[0].cfg.width,
+        }
+    }
+
+    /// Returns the height of the luma plane.
+    pub fn height(&self) -> usize {
+        match self {
+            DynFrame::U8 { frame, .. } => frame.planes[0].cfg.height,
+            DynFrame::U16 { frame, .. } => frame.planes[0].cfg.height,
+        }
+    }
+
+    /// Returns the chroma sampling of the frame.
+    ///
+    /// # Errors
+    ///
+    /// - If the chroma sampling of the frame cannot be determined, see
+    ///   [`Frame::chroma_sampling`]
+    pub fn chroma_sampling(&self) -> Result<ChromaSampling, Error> {
+        match self {
+            DynFrame::U8 { frame, .. } => frame.chroma_sampling(),
+            DynFrame::U16 { frame, .. } => frame.chroma_sampling(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    use wasm_bindgen_test::*;
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    #[cfg(feature = "std")]
+    fn psnr() {
+        let a = Frame::<u8>::new_with_padding(16, 16, ChromaSampling::Cs420, 0);
+        let mut b = a.clone();
+
+        let psnr = a.psnr(&b, 8).unwrap();
+        assert!(psnr.iter().all(|p| p.is_infinite()));
+
+        // One pixel off by one in a 16x16 luma plane: mse = 1/256
+        b.planes[0].data_origin_mut()[0] += 1;
+        let psnr = a.psnr(&b, 8).unwrap();
+        let expected = 10.0 * (255.0f64 * 255.0 * 256.0).log10();
+        assert!((psnr[0] - expected).abs() < 1e-9);
+        assert!(psnr[1].is_infinite() && psnr[2].is_infinite());
+
+        let mono = Frame::<u8>::new_with_padding(16, 16, ChromaSampling::Cs400, 0);
+        let psnr = mono.psnr(&mono, 8).unwrap();
+        assert!(psnr[0].is_infinite());
+        assert!(psnr[1].is_nan() && psnr[2].is_nan());
+
+        let other = Frame::<u8>::new_with_padding(32, 16, ChromaSampling::Cs420, 0);
+        assert!(a.psnr(&other, 8).is_err());
+
+        for bit_depth in [0, 10, 64] {
+            assert_eq!(
+                a.psnr(&a, bit_depth).err(),
+                Some(Error::UnsupportedBitDepth { bit_depth })
+            );
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn rotate_90() {
+        let frame = Frame::<u8>::new_with_padding(32, 16, ChromaSampling::Cs420, 8);
+
+        for rotated in [frame.rotate_90_cw(), frame.rotate_90_ccw()] {
+            let rotated = rotated.unwrap();
+            let luma = &rotated.planes[0].cfg;
+            assert_eq!((luma.width, luma.height), (16, 32));
+            for chroma in &rotated.planes[1..] {
+                let cfg = &chroma.cfg;
+                assert_eq!((cfg.width, cfg.height), (8, 16));
+                assert_eq!((cfg.xdec, cfg.ydec), (1, 1));
+            }
+            rotated.validate().unwrap();
+        }
+
+        let frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs422, 0);
+        assert_eq!(frame.rotate_90_cw(), Err(Error::InvalidChromaSampling));
+        assert_eq!(frame.rotate_90_ccw(), Err(Error::InvalidChromaSampling));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn reinit() {
+        let mut frame = Frame::<u16>::new_with_padding(64, 32, ChromaSampling::Cs420, 16);
+        let ptrs: Vec<_> = frame.planes.iter().map(|p| p.data.as_ptr()).collect();
+
+        frame.planes[0].data_origin_mut()[0] = 42;
+        frame.reinit(64, 32, ChromaSampling::Cs420, 16);
+        assert!(ptrs
+            .iter()
+            .zip(&frame.planes)
+            .all(|(&ptr, p)| ptr == p.data.as_ptr()));
+        assert_eq!(frame.planes[0].p(0, 0), 42);
+
+        frame.reinit(32, 32, ChromaSampling::Cs444, 0);
+        assert_eq!(
+            frame,
+            Frame::<u16>::new_with_padding(32, 32, ChromaSampling::Cs444, 0)
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn convert_subsampling() {
+        let mut frame = Frame::<u8>::new_with_padding(8, 8, ChromaSampling::Cs444, 0);
+        for (y, row) in frame.planes[1].rows_iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = (y * 8 + x) as u8;
+            }
+        }
+
+        let cs420 = frame.convert_subsampling(ChromaSampling::Cs420, 8);
+        assert_eq!(cs420.planes[0], frame.planes[0]);
+        let u = &cs420.planes[1];
+        assert_eq!((u.cfg.width, u.cfg.height), (4, 4));
+        assert_eq!((u.cfg.xdec, u.cfg.ydec), (1, 1));
+        // (0 + 1 + 8 + 9 + 2) / 4
+        assert_eq!(u.p(0, 0), 5);
+        assert_eq!(u.p(3, 3), 59);
+        assert!(cs420.planes[2].iter().all(|p| p == 128));
+
+        let cs444 = cs420.convert_subsampling(ChromaSampling::Cs444, 8);
+        let u = &cs444.planes[1];
+        assert_eq!((u.cfg.width, u.cfg.height), (8, 8));
+        assert_eq!([u.p(0, 0), u.p(1, 1), u.p(2, 0)], [5, 5, 7]);
+        assert_eq!(
+            cs444.convert_subsampling(ChromaSampling::Cs420, 8),
+            cs420.convert_subsampling(ChromaSampling::Cs420, 8)
+        );
+
+        let mono = frame.convert_subsampling(ChromaSampling::Cs400, 10);
+        assert_eq!(
+            mono,
+            Frame::new_with_padding(8, 8, ChromaSampling::Cs400, 0)
+                .convert_subsampling(ChromaSampling::Cs400, 10)
+        );
+        assert_eq!(mono.planes[1].cfg.width, 0);
+
+        let frame = Frame::<u16>::new_with_padding(8, 8, ChromaSampling::Cs400, 0);
+        let cs422 = frame.convert_subsampling(ChromaSampling::Cs422, 10);
+        assert_eq!(
+            (cs422.planes[2].cfg.width, cs422.planes[2].cfg.height),
+            (4, 8)
+        );
+        assert!(cs422.planes[2].iter().all(|p| p == 512));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn into_luma() {
+        let mut frame = Frame::<u8>::new_with_padding(16, 16, ChromaSampling::Cs420, 8);
+        frame.luma_mut().data_origin_mut()[0] = 42;
+        let luma = frame.luma().clone();
+        let luma_ptr = frame.luma().data.as_ptr();
+
+        let mono = frame.into_luma();
+        assert_eq!(mono.luma(), &luma);
+        assert_eq!(mono.luma().data.as_ptr(), luma_ptr);
+
+        let mut expected = Frame::<u8>::new_with_padding(16, 16, ChromaSampling::Cs400, 8);
+        expected.luma_mut().data_origin_mut()[0] = 42;
+        assert_eq!(mono, expected);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn new_filled() {
+        let frame = Frame::<u16>::new_filled(16, 8, ChromaSampling::Cs422, 4, [64, 512, 940]);
+
+        for (plane, value) in frame.planes.iter().zip([64, 512, 940]) {
+            assert!(plane.iter().all(|p| p == value));
+        }
+        assert_eq!(frame.planes[1].iter().count(), 8 * 8);
+
+        // The padding is filled as well
+        assert!(frame.planes[0].data.iter().all(|&p| p == 64));
+        assert_eq!(frame.planes[0].cfg.xpad, 4);
+
+        let mono = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs400, 0, [16, 0, 0]);
+        assert!(mono.planes[0].iter().all(|p| p == 16));
+        assert_eq!(mono.planes[1].iter().count(), 0);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    #[cfg(feature = "std")]
+    fn planar_io() {
+        let frame = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs420, 8, [0x0102, 3, 4]);
+
+        let mut yuv = Vec::new();
+        frame.write_planar(&mut yuv, PlaneOrder::Yuv).unwrap();
+        assert_eq!(yuv.len(), (64 + 16 + 16) * 2);
+        assert_eq!(&yuv[..2], &[0x02, 0x01]);
+        assert_eq!(&yuv[128..130], &[3, 0]);
+        assert_eq!(&yuv[160..162], &[4, 0]);
+
+        let mut yvu = Vec::new();
+        frame.write_planar(&mut yvu, PlaneOrder::Yvu).unwrap();
+        assert_eq!(&yvu[128..130], &[4, 0]);
+        assert_eq!(&yvu[160..162], &[3, 0]);
+
+        let mut read = Frame::<u16>::new_with_padding(8, 8, ChromaSampling::Cs420, 0);
+        read.read_planar(&mut &yvu[..], PlaneOrder::Yvu).unwrap();
+        for (a, b) in read.planes.iter().zip(&frame.planes) {
+            assert!(a.iter().eq(b.iter()));
+        }
+        assert!(read.read_planar(&mut &yvu[1..], PlaneOrder::Yvu).is_err());
+
+        let mono = Frame::<u8>::new_filled(8, 8, ChromaSampling::Cs400, 0, [16, 0, 0]);
+        let mut y = Vec::new();
+        mono.write_planar(&mut y, PlaneOrder::Yvu).unwrap();
+        assert_eq!(y, [16; 64]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn approx_eq() {
+        let a = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs420, 0, [100, 200, 300]);
+        let mut b = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs420, 16, [100, 200, 300]);
+        assert_eq!(a.max_abs_diff(&b), Some(0));
+        assert!(a.approx_eq(&b, 0));
+
+        b.planes[2].data_origin_mut()[3] = 303;
+        b.planes[0].data_origin_mut()[0] = 98;
+        assert_eq!(a.max_abs_diff(&b), Some(3));
+        assert!(!a.approx_eq(&b, 2));
+        assert!(a.approx_eq(&b, 3));
+
+        let c = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs444, 0, [100, 200, 300]);
+        assert_eq!(a.max_abs_diff(&c), None);
+        assert!(!a.approx_eq(&c, u16::MAX));
+
+        let a = a.with_alpha(10);
+        assert_eq!(a.max_abs_diff(&b), None);
+        assert!(!b.approx_eq(&a, u16::MAX));
+        let mut b = b.with_alpha(10);
+        b.alpha_mut().unwrap().data_origin_mut()[0] = 1000;
+        assert_eq!(a.max_abs_diff(&b), Some(23));
+        assert!(!a.approx_eq(&b, 3));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn new_from_fn() {
+        let mut calls = Vec::new();
+        let frame = Frame::<u16>::new_from_fn(8, 8, ChromaSampling::Cs420, 4, |p, x, y| {
+            calls.push((p, x, y));
+            (p * 1000 + y * 10 + x) as u16
+        });
+
+        assert_eq!(calls.len(), 64 + 16 + 16);
+        assert_eq!(&calls[..3], &[(0, 0, 0), (0, 1, 0), (0, 2, 0)]);
+        assert_eq!(calls[8], (0, 0, 1));
+        assert_eq!(calls[64], (1, 0, 0));
+
+        assert_eq!(frame.planes[0].p(7, 7), 77);
+        assert_eq!(frame.planes[1].p(3, 2), 1023);
+        assert_eq!(frame.planes[2].p(0, 1), 2010);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn swap() {
+        let mut a = Frame::<u8>::new_filled(8, 8, ChromaSampling::Cs420, 0, [1, 2, 3]);
+        let mut b = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs444, 0, [4, 5, 6]);
+        let (a_ptr, b_ptr) = (a.planes[0].data.as_ptr(), b.planes[0].data.as_ptr());
+
+        a.swap_with(&mut b);
+        assert_eq!(
+            (a.planes[0].data.as_ptr(), b.planes[0].data.as_ptr()),
+            (b_ptr, a_ptr)
+        );
+        assert_eq!(a.planes[0].cfg.width, 16);
+        assert_eq!(b.planes[1].p(0, 0), 2);
+
+        a.swap_uv();
+        assert_eq!((a.planes[1].p(0, 0), a.planes[2].p(0, 0)), (6, 5));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn parts() {
+        for chroma_sampling in [
+            ChromaSampling::Cs420,
+            ChromaSampling::Cs422,
+            ChromaSampling::Cs444,
+            ChromaSampling::Cs400,
+        ] {
+            let frame = Frame::<u8>::new_from_fn(16, 8, chroma_sampling, 4, |i, x, y| {
+                (i * 64 + x + y) as u8
+            });
+            let (y, u, v, cs) = frame.clone().into_parts().unwrap();
+            assert_eq!(cs, chroma_sampling);
+            assert_eq!(u.is_some(), chroma_sampling != ChromaSampling::Cs400);
+            assert_eq!(Frame::from_parts(y, u, v, cs).unwrap(), frame);
+        }
+
+        let (y, u, v, _) = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs420, 0)
+            .into_parts()
+            .unwrap();
+        assert_eq!(
+            Frame::from_parts(y.clone(), None, None, ChromaSampling::Cs420),
+            Err(Error::InvalidChromaSampling)
+        );
+        assert_eq!(
+            Frame::from_parts(y.clone(), u.clone(), v.clone(), ChromaSampling::Cs422),
+            Err(Error::InvalidChromaSampling)
+        );
+        let small = Plane::new(4, 4, 1, 1, 0, 0);
+        assert_eq!(
+            Frame::from_parts(y, Some(small), v, ChromaSampling::Cs420),
+            Err(Error::DimensionMismatch {
+                expected: (8, 4),
+                found: (4, 4)
+            })
+        );
+
+        // Chroma subsampled vertically only has no `ChromaSampling`.
+        let mut frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs420, 0);
+        frame.planes[1] = Plane::new(16, 4, 0, 1, 0, 0);
+        frame.planes[2] = Plane::new(16, 4, 0, 1, 0, 0);
+        assert_eq!(frame.chroma_sampling(), Err(Error::InvalidChromaSampling));
+        assert_eq!(frame.validate(), Err(Error::InconsistentFrame));
+        assert!(frame.into_parts().is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn dyn_frame() {
+        let frame = DynFrame::new_with_padding(32, 16, ChromaSampling::Cs422, 0, 8).unwrap();
+        assert!(matches!(frame, DynFrame::U8 { .. }));
+        assert_eq!(frame.bit_depth(), 8);
+        assert_eq!(frame.width(), 32);
+        assert_eq!(frame.height(), 16);
+        assert_eq!(frame.chroma_sampling(), Ok(ChromaSampling::Cs422));
+
+        let frame = DynFrame::new_with_padding(32, 16, ChromaSampling::Cs400, 0, 10).unwrap();
+        assert!(matches!(frame, DynFrame::U16 { .. }));
+        assert_eq!(frame.bit_depth(), 10);
+        assert_eq!(frame.chroma_sampling(), Ok(ChromaSampling::Cs400));
+        assert_ne!(
+            DynFrame::new_with_padding(32, 16, ChromaSampling::Cs400, 0, 12).unwrap(),
+            frame
+        );
+
+        for bit_depth in [0, 17, 40] {
+            assert_eq!(
+                DynFrame::new_with_padding(32, 16, ChromaSampling::Cs420, 0, bit_depth),
+                Err(Error::UnsupportedBitDepth { bit_depth })
+            );
+        }
+
+        let frame = Frame::<u16>::new_with_padding(16, 8, ChromaSampling::Cs420, 0);
+        assert_eq!(
+            DynFrame::from_u16(frame.clone(), 12).unwrap().bit_depth(),
+            12
+        );
+        assert_eq!(
+            DynFrame::from_u16(frame, 8),
+            Err(Error::UnsupportedBitDepth { bit_depth: 8 })
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn byte_len() {
+        let frame = Frame::<u16>::new_with_padding(16, 8, ChromaSampling::Cs420, 0);
+        assert_eq!(frame.visible_byte_len(), (16 * 8 + 2 * 8 * 4) * 2);
+        assert_eq!(
+            frame.allocated_byte_len(),
+            frame.planes.iter().map(|p| p.data.len() * 2).sum::<usize>()
+        );
+
+        let frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs400, 8);
+        assert_eq!(frame.visible_byte_len(), 16 * 8);
+        assert_eq!(
+            frame.allocated_byte_len(),
+            frame.planes.iter().map(|p| p.data.len()).sum::<usize>()
+        );
+        assert!(frame.allocated_byte_len() > frame.visible_byte_len());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn alpha() {
+        let frame = Frame::<u16>::new_with_padding(16, 8, ChromaSampling::Cs420, 0);
+        assert!(frame.alpha().is_none());
+
+        let mut frame = frame.with_alpha(10);
+        let alpha = frame.alpha().unwrap();
+        assert_eq!(alpha.cfg, frame.planes[0].cfg);
+        assert!(alpha.rows_iter().flatten().all(|&a| a == 1023));
+        assert_eq!(frame.visible_byte_len(), (2 * 16 * 8 + 2 * 8 * 4) * 2);
+
+        frame.alpha_mut().unwrap().fill_with(|x, _| x as u16);
+        let rotated = frame.rotate_90_cw().unwrap();
+        let alpha = rotated.alpha().unwrap();
+        assert_eq!((alpha.cfg.width, alpha.cfg.height), (8, 16));
+        assert_eq!(alpha.p(0, 15), 15);
+
+        frame.map_in_place(|p| p + 1);
+        assert_eq!(frame.alpha().unwrap().p(15, 0), 16);
+
+        let luma = frame.into_luma();
+        assert!(luma.alpha().is_some());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn fields() {
+        let frame = Frame::<u8>::new_from_fn(16, 8, ChromaSampling::Cs420, 0, |i, x, y| {
+            (i * 64 + x + y * 8) as u8
+        });
+
+        let (top, bottom) = frame.separate_fields();
+        assert_eq!(top.planes[0].cfg.height, 4);
+        assert_eq!(top.planes[1].cfg.height, 2);
+        assert_eq!(bottom.planes[1].p(0, 0), 64 + 8);
+
+        let woven = Frame::weave_fields(&top, &bottom).unwrap();
+        assert_eq!(woven.max_abs_diff(&frame), Some(0));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn rescale_bit_depth() {
+        let mut frame = Frame::<u16>::new_filled(16, 8, ChromaSampling::Cs420, 0, [64, 512, 960])
+            .with_alpha(10);
+
+        frame.rescale_bit_depth(10, 12).unwrap();
+        assert_eq!(frame.planes[0].p(0, 0), 256);
+        assert_eq!(frame.planes[2].p(0, 0), 3840);
+        assert_eq!(frame.alpha().unwrap().p(0, 0), 4092);
+
+        assert_eq!(
+            frame.rescale_bit_depth(12, 40),
+            Err(Error::UnsupportedBitDepth { bit_depth: 40 })
+        );
+        assert_eq!(frame.planes[0].p(0, 0), 256);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_pattern() {
+        let gray =
+            Frame::<u16>::test_pattern(16, 8, ChromaSampling::Cs420, 0, 10, TestPattern::SolidGray)
+                .unwrap();
+        assert!(gray.planes.iter().flat_map(|p| p.iter()).all(|p| p == 512));
+
+        let ramp = Frame::<u8>::test_pattern(
+            16,
+            8,
+            ChromaSampling::Cs420,
+            0,
+            8,
+            TestPattern::HorizontalGradient,
+        )
+        .unwrap();
+        assert_eq!((ramp.planes[0].p(0, 3), ramp.planes[0].p(15, 3)), (0, 255));
+        assert_eq!(ramp.planes[1].p(3, 0), 128);
+
+        let ramp = Frame::<u8>::test_pattern(
+            16,
+            8,
+            ChromaSampling::Cs420,
+            0,
+            8,
+            TestPattern::VerticalGradient,
+        )
+        .unwrap();
+        assert_eq!((ramp.planes[0].p(3, 0), ramp.planes[0].p(3, 7)), (0, 255));
+
+        let checker = Frame::<u8>::test_pattern(
+            16,
+            8,
+            ChromaSampling::Cs444,
+            0,
+            8,
+            TestPattern::Checkerboard { size: 4 },
+        )
+        .unwrap();
+        assert_eq!(checker.planes[0].p(3, 3), 255);
+        assert_eq!(checker.planes[0].p(4, 3), 0);
+        assert_eq!(checker.planes[0].p(4, 4), 255);
+
+        let bars =
+            Frame::<u16>::test_pattern(16, 8, ChromaSampling::Cs420, 0, 10, TestPattern::ColorBars)
+                .unwrap();
+        assert_eq!(bars.planes[0].p(0, 0), 180 << 2);
+        assert_eq!(bars.planes[0].p(15, 0), 16 << 2);
+        assert_eq!(bars.planes[1].p(1, 0), 44 << 2);
+        assert_eq!(bars.planes[2].p(7, 3), 128 << 2);
+
+        let bars =
+            Frame::<u8>::test_pattern(16, 8, ChromaSampling::Cs444, 0, 4, TestPattern::ColorBars)
+                .unwrap();
+        assert_eq!(bars.planes[0].p(0, 0), 180 >> 4);
+        for bit_depth in [0, 10] {
+            assert_eq!(
+                Frame::<u8>::test_pattern(
+                    16,
+                    8,
+                    ChromaSampling::Cs420,
+                    0,
+                    bit_depth,
+                    TestPattern::ColorBars
+                ),
+                Err(Error::UnsupportedBitDepth { bit_depth })
+            );
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn try_new_with_padding() {
+        let frame = Frame::<u16>::try_new_with_padding(64, 32, ChromaSampling::Cs420, 8).unwrap();
+        assert_eq!(
+            frame,
+            Frame::new_with_padding(64, 32, ChromaSampling::Cs420, 8)
+        );
+
+        // Dimensions whose product overflows on 32-bit targets
+        let (width, height) = (1 << 17, 1 << 16);
+        if usize::BITS == 32 {
+            assert!(
+                Frame::<u8>::try_new_with_padding(width, height, ChromaSampling::Cs420, 0).is_err()
+            );
+        }
+        assert!(matches!(
+            Frame::<u16>::try_new_with_padding(usize::MAX >> 1, 1 << 8, ChromaSampling::Cs400, 0),
+            Err(Error::AllocationTooLarge { .. })
+        ));
+        assert!(matches!(
+            Frame::<u16>::check_new_with_padding(usize::MAX >> 1, 1 << 8, ChromaSampling::Cs400, 0),
+            Err(Error::AllocationTooLarge { .. })
+        ));
+        // Fits in the address space of 64-bit targets only, but is never
+        // allocated
+        assert_eq!(
+            Frame::<u16>::check_new_with_padding(1 << 16, 1 << 16, ChromaSampling::Cs420, 64)
+                .is_ok(),
+            usize::BITS == 64
+        );
+        assert!(
+            Frame::<u8>::try_new_with_padding(usize::MAX - 3, 8, ChromaSampling::Cs444, 0).is_err()
+        );
+        assert!(PlaneConfig::try_new(8, 8, 0, 0, usize::MAX, 0, 1).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn convert_with() {
+        let frame = Frame::<u16>::new_filled(16, 8, ChromaSampling::Cs422, 8, [1023, 512, 4])
+            .with_alpha(10);
+
+        let converted: Frame<u8> = frame.convert_with(|p| (p >> 2) as u8);
+        assert_eq!(converted.chroma_sampling(), Ok(ChromaSampling::Cs422));
+        for (a, b) in converted.planes.iter().zip(&frame.planes) {
+            assert_eq!(
+                (a.cfg.width, a.cfg.height, a.cfg.xpad),
+                (b.cfg.width, b.cfg.height, b.cfg.xpad)
+            );
+        }
+        assert_eq!(converted.planes[0].p(3, 3), 255);
+        assert_eq!(converted.planes[1].p(3, 3), 128);
+        assert_eq!(converted.planes[2].p(3, 3), 1);
+        assert_eq!(converted.alpha().unwrap().p(0, 0), 255);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn new_with_chroma_padding() {
+        let frame = Frame::<u8>::new_with_chroma_padding(64, 32, ChromaSampling::Cs420, 8, 16, 6);
+        assert_eq!((frame.planes[0].cfg.xpad, frame.planes[0].cfg.ypad), (8, 8));
+        for plane in &frame.planes[1..] {
+            assert_eq!((plane.cfg.width, plane.cfg.height), (32, 16));
+            assert_eq!((plane.cfg.xpad, plane.cfg.ypad), (16, 6));
+            assert_eq!(plane.cfg.validate(), Ok(()));
+        }
+
+        let derived = Frame::<u8>::new_with_chroma_padding(64, 32, ChromaSampling::Cs420, 8, 4, 4);
+        assert_eq!(
+            derived,
+            Frame::new_with_padding(64, 32, ChromaSampling::Cs420, 8)
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn validate() {
+        for chroma_sampling in [
+            ChromaSampling::Cs420,
+            ChromaSampling::Cs422,
+            ChromaSampling::Cs444,
+            ChromaSampling::Cs400,
+        ] {
+            let frame = Frame::<u8>::new_with_padding(20, 12, chroma_sampling, 8).with_alpha(8);
+            assert_eq!(frame.validate(), Ok(()));
+        }
+
+        let mut frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs420, 0);
+        frame.planes[2] = Plane::new(4, 4, 1, 1, 0, 0);
+        assert_eq!(frame.validate(), Err(Error::InconsistentFrame));
+
+        let mut frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs400, 0);
+        frame.planes[1] = Plane::new(8, 4, 1, 1, 0, 0);
+        assert_eq!(frame.validate(), Err(Error::InconsistentFrame));
+
+        let mut frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs444, 0);
+        frame.alpha = Some(Plane::new(8, 8, 0, 0, 0, 0));
+        assert_eq!(frame.validate(), Err(Error::InconsistentFrame));
+
+        let mut frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs444, 0);
+        frame.planes[0].cfg.stride = 8;
+        assert_eq!(frame.validate(), Err(Error::InvalidPlaneConfig));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn apply_into() {
+        let src = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs420, 0, [10, 20, 30]);
+        let mut dst = Frame::new_with_padding(16, 8, ChromaSampling::Cs420, 8);
+
+        src.apply_into(&mut dst, |p| 255 - p).unwrap();
+        assert_eq!(dst.planes[0].p(15, 7), 245);
+        assert_eq!(dst.planes[1].p(7, 3), 235);
+        assert_eq!(dst.planes[2].p(0, 0), 225);
+
+        let mut wrong = Frame::new_filled(16, 8, ChromaSampling::Cs444, 0, [0; 3]);
+        let before = wrong.clone();
+        assert_eq!(
+            src.apply_into(&mut wrong, |p| p),
+            Err(Error::DimensionMismatch {
+                expected: (8, 4),
+                found: (16, 8)
+            })
+        );
+        assert_eq!(wrong, before);
+
+        let mut dst = dst.with_alpha(8);
+        dst.alpha_mut().unwrap().fill(7);
+        assert_eq!(
+            src.apply_into(&mut dst, |p| p),
+            Err(Error::InconsistentFrame)
+        );
+        let src = src.with_alpha(8);
+        src.apply_into(&mut dst, |p| 255 - p).unwrap();
+        assert!(dst.alpha().unwrap().rows_iter().flatten().all(|&a| a == 0));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn fill_rect() {
+        let mut frame = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs420, 0, [1, 2, 3]);
+
+        // Letterbox bar on an odd boundary
+        frame.fill_rect(0, 5, 16, 3, [16, 128, 128]).unwrap();
+        assert_eq!(frame.planes[0].p(0, 4), 1);
+        assert_eq!(frame.planes[0].p(15, 5), 16);
+        assert_eq!(frame.planes[1].p(0, 1), 2);
+        assert_eq!(frame.planes[1].p(7, 2), 128);
+        assert_eq!(frame.planes[2].p(7, 3), 128);
+
+        assert!(frame.fill_rect(8, 0, 9, 1, [0; 3]).is_err());
+
+        let mut mono = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs400, 0, [1, 2, 3]);
+        mono.fill_rect(0, 0, 16, 8, [0; 3]).unwrap();
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn fits_bit_depth() {
+        let mut frame = Frame::<u16>::new_filled(16, 8, ChromaSampling::Cs420, 8, [64, 512, 940]);
+        assert_eq!(frame.max_pixel_value(), Some(940));
+        assert!(frame.fits_bit_depth(10));
+        assert!(!frame.fits_bit_depth(8));
+
+        frame.planes[2][(3, 2)] = 4000;
+        assert_eq!(frame.max_pixel_value(), Some(4000));
+        assert!(!frame.fits_bit_depth(10));
+        assert!(frame.fits_bit_depth(12));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn from_strided_planes() {
+        let stride = |s| NonZeroUsize::new(s).unwrap();
+        let y: Vec<u8> = (0..6 * 4).map(|i| (i % 6 + i / 6 * 10) as u8).collect();
+        let u = [1u8; 4 * 2];
+        let v = [2u8; 4 * 2];
+
+        let frame = Frame::<u8>::from_strided_planes(
+            5,
+            4,
+            ChromaSampling::Cs420,
+            0,
+            8,
+            (&y, stride(6)),
+            Some((&u, stride(4))),
+            Some((&v, stride(4))),
+        )
+        .unwrap();
+        assert_eq!(
+            frame.planes[0].rows_iter().nth(2).unwrap()[..5],
+            [20, 21, 22, 23, 24]
+        );
+        // The padding replicates the last visible column rather than the
+        // extra bytes of the source stride
+        assert_eq!(frame.planes[0].p(5, 2), 24);
+        assert!(frame.planes[1].iter().all(|p| p == 1));
+        assert!(frame.planes[2].iter().all(|p| p == 2));
+
+        let y16: Vec<u8> = [64u16, 940].iter().flat_map(|p| p.to_le_bytes()).collect();
+        let frame = Frame::<u16>::from_strided_planes(
+            2,
+            1,
+            ChromaSampling::Cs400,
+            0,
+            10,
+            (&y16, stride(4)),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(frame.planes[0].rows_iter().next().unwrap()[..2], [64, 940]);
+
+        assert_eq!(
+            Frame::<u8>::from_strided_planes(
+                5,
+                4,
+                ChromaSampling::Cs420,
+                0,
+                8,
+                (&y, stride(6)),
+                Some((&u, stride(4))),
+                None,
+            )
+            .err(),
+            Some(Error::InvalidChromaSampling)
+        );
+        assert_eq!(
+            Frame::<u8>::from_strided_planes(
+                5,
+                4,
+                ChromaSampling::Cs400,
+                0,
+                8,
+                (&y, stride(4)),
+                None,
+                None,
+            )
+            .err(),
+            Some(Error::InvalidStride {
+                stride: 4,
+                row_len: 5
+            })
+        );
+        assert_eq!(
+            Frame::<u8>::from_strided_planes(
+                5,
+                4,
+                ChromaSampling::Cs400,
+                0,
+                8,
+                (&y[..20], stride(6)),
+                None,
+                None,
+            )
+            .err(),
+            Some(Error::DataLength {
+                expected: 24,
+                found: 20
+            })
+        );
+        assert_eq!(
+            Frame::<u8>::from_strided_planes(
+                5,
+                4,
+                ChromaSampling::Cs400,
+                0,
+                10,
+                (&y, stride(6)),
+                None,
+                None,
+            )
+            .err(),
+            Some(Error::UnsupportedBitDepth { bit_depth: 10 })
+        );
+
+        // Sizes whose product overflows must not pass the length check
+        let huge = usize::MAX / 2 + 1;
+        assert_eq!(
+            Frame::<u16>::from_strided_planes(
+                huge,
+                1,
+                ChromaSampling::Cs400,
+                0,
+                10,
+                (&y, stride(6)),
+                None,
+                None,
+            )
+            .err(),
+            Some(Error::AllocationTooLarge {
+                width: huge,
+                height: 1
+            })
+        );
+        assert_eq!(
+            Frame::<u8>::from_strided_planes(
+                5,
+                4,
+                ChromaSampling::Cs400,
+                0,
+                8,
+                (&y, stride(huge)),
+                None,
+                None,
+            )
+            .err(),
+            Some(Error::AllocationTooLarge {
+                width: 5,
+                height: 4
+            })
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn downsample_2x() {
+        let mut frame = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs420, 8, [100, 50, 200]);
+        frame.planes[0][(0, 0)] = 104;
+        let frame = frame.with_alpha(8);
+
+        let half = frame.downsample_2x().unwrap();
+        assert_eq!(half.chroma_sampling(), Ok(ChromaSampling::Cs420));
+        assert_eq!(
+            (half.planes[0].cfg.width, half.planes[0].cfg.height),
+            (8, 4)
+        );
+        assert_eq!(
+            (half.planes[1].cfg.width, half.planes[1].cfg.height),
+            (4, 2)
+        );
+        assert_eq!(half.planes[0][(0, 0)], 101);
+        assert!(half.planes[2].iter().all(|p| p == 200));
+        assert!(half.alpha().unwrap().iter().all(|p| p == 255));
+        assert!(half.validate().is_ok());
+
+        // 8x4 -> 4x2, after which the 2x1 chroma planes cannot be halved
+        // along with the luma plane
+        let pyramid = frame.pyramid(5);
+        assert_eq!(pyramid.len(), 2);
+        assert_eq!(pyramid[1].planes[0].cfg.width, 4);
+        assert_eq!(
+            pyramid[1].downsample_2x().err(),
+            Some(Error::InconsistentFrame)
+        );
+
+        let mono = Frame::<u16>::new_filled(16, 16, ChromaSampling::Cs400, 0, [512; 3]);
+        let pyramid = mono.pyramid(8);
+        assert_eq!(pyramid.len(), 4);
+        assert_eq!(pyramid[3].chroma_sampling(), Ok(ChromaSampling::Cs400));
+        assert_eq!(pyramid[3].planes[0].iter().collect::<Vec<_>>(), [512]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    #[cfg(feature = "std")]
+    fn rgb8() {
+        let bars =
+            Frame::<u8>::test_pattern(64, 8, ChromaSampling::Cs444, 0, 8, TestPattern::ColorBars)
+                .unwrap();
+        let rgb = bars.to_rgb8(64, 8, 8, ColorMatrix::Bt709).unwrap();
+        assert_eq!(rgb.len(), 64 * 8 * 3);
+        // 75% white, yellow and blue bars
+        assert_eq!(rgb[..3], [191, 191, 191]);
+        for (i, expected) in [(1, [191, 191, 0]), (6, [0, 0, 191])] {
+            for (value, expected) in rgb[i * 8 * 3..][..3].iter().zip(expected) {
+                assert!((i32::from(*value) - expected).abs() <= 2);
+            }
+        }
+
+        let mono = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs400, 0, [940, 0, 0]);
+        assert!(mono
+            .to_rgb8(8, 8, 10, ColorMatrix::Bt2020)
+            .unwrap()
+            .iter()
+            .all(|&c| c == 255));
+        assert_eq!(
+            mono.to_rgb8(8, 8, 0, ColorMatrix::Bt709),
+            Err(Error::UnsupportedBitDepth { bit_depth: 0 })
+        );
+        assert_eq!(
+            mono.to_rgb8(8, 8, 64, ColorMatrix::Bt709),
+            Err(Error::UnsupportedBitDepth { bit_depth: 64 })
+        );
+        assert!(mono.to_rgb8(9, 8, 10, ColorMatrix::Bt709).is_err());
+
+        // Round trip through 4:2:0 with blocks of uniform color
+        let (width, height) = (12, 6);
+        let rgb: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let block = (i % width) / 2 + (i / width) / 2 * 6;
+                [(block * 14) as u8, 200 - (block * 9) as u8, 60]
+            })
+            .collect();
+        for matrix in [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020] {
+            let frame =
+                Frame::<u16>::from_rgb8(width, height, ChromaSampling::Cs420, 8, 10, &rgb, matrix)
+                    .unwrap();
+            assert!(frame.fits_bit_depth(10));
+            let round_trip = frame.to_rgb8(width, height, 10, matrix).unwrap();
+            assert_eq!(round_trip.len(), rgb.len());
+            for (a, b) in round_trip.iter().zip(&rgb) {
+                assert!((i32::from(*a) - i32::from(*b)).abs() <= 2);
+            }
+        }
+
+        assert_eq!(
+            Frame::<u8>::from_rgb8(
+                4,
+                4,
+                ChromaSampling::Cs420,
+                0,
+                8,
+                &[0; 47],
+                ColorMatrix::Bt709
+            )
+            .err(),
+            Some(Error::DataLength {
+                expected: 48,
+                found: 47
+            })
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn is_flat() {
+        let mut frame = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs420, 8, [16, 128, 128]);
+        assert!(frame.is_flat());
+        frame.planes[2][(1, 1)] = 129;
+        assert!(!frame.is_flat());
+
+        let mono = Frame::<u8>::new_filled(16, 8, ChromaSampling::Cs400, 0, [235, 0, 0]);
+        assert!(mono.is_flat());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn dimensions() {
+        let tests = [
+            (ChromaSampling::Cs420, Some((12, 8))),
+            (ChromaSampling::Cs422, Some((12, 16))),
+            (ChromaSampling::Cs444, Some((24, 16))),
+            (ChromaSampling::Cs400, None),
+        ];
+
+        for (chroma_sampling, expected) in tests {
+            let frame = Frame::<u16>::new_with_padding(21, 13, chroma_sampling, 8);
+            assert_eq!(frame.luma_dimensions(), (24, 16));
+            assert_eq!(frame.chroma_dimensions(), expected);
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn scene_change_score() {
+        let black = Frame::<u16>::new_filled(16, 16, ChromaSampling::Cs420, 0, [0, 512, 512]);
+        let white = Frame::<u16>::new_filled(16, 16, ChromaSampling::Cs420, 0, [1023, 512, 512]);
+        let mut half = black.clone();
+        for row in half.planes[0].rows_iter_mut().take(8) {
+            row.fill(1023);
+        }
+
+        for downsample in [false, true] {
+            let score = |a: &Frame<u16>, b| a.scene_change_score(b, 10, downsample).unwrap();
+            assert!(score(&black, &black).abs() < f64::EPSILON);
+            assert!((score(&white, &black) - 1.0).abs() < f64::EPSILON);
+            assert!((score(&half, &black) - 0.5).abs() < f64::EPSILON);
+        }
+
+        let small = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs420, 0, [0, 512, 512]);
+        for downsample in [false, true] {
+            assert_eq!(
+                black.scene_change_score(&small, 10, downsample).err(),
+                Some(Error::DimensionMismatch {
+                    expected: (16, 16),
+                    found: (8, 8)
+                })
+            );
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn info() {
+        let frame = Frame::<u16>::new_with_padding(64, 32, ChromaSampling::Cs422, 8);
+        let info = frame.info(10, ChromaSamplePosition::Left).unwrap();
+        assert_eq!(
+            info,
+            FrameInfo {
+                width: 64,
+                height: 32,
+                chroma_sampling: ChromaSampling::Cs422,
+                chroma_sample_position: ChromaSamplePosition::Left,
+                bit_depth: 10,
+            }
+        );
+
+        let mono = Frame::<u16>::new_with_padding(64, 32, ChromaSampling::Cs400, 8);
+        assert_ne!(mono.info(10, ChromaSamplePosition::Left).unwrap(), info);
+        assert_ne!(frame.info(12, ChromaSamplePosition::Left).unwrap(), info);
+        assert_ne!(frame.info(10, ChromaSamplePosition::TopLeft).unwrap(), info);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn layout_report() {
+        let frame = Frame::<u16>::new_with_padding(40, 16, ChromaSampling::Cs420, 8);
+        let layouts = frame.layout_report();
+        assert_eq!(layouts.len(), 3);
+        let (luma, u, v) = (layouts[0], layouts[1], layouts[2]);
+
+        // The left padding and the stride are aligned to 64 bytes
+        assert_eq!(
+            luma,
+            PlaneLayout {
+                width: 40,
+                height: 16,
+                stride: 96,
+                pad_left: 32,
+                pad_right: 24,
+                pad_top: 8,
+                pad_bottom: 8,
+                len: 96 * 32,
+                origin: 8 * 96 + 32,
+            }
+        );
+        assert_eq!(u, v);
+        assert_eq!((u.width, u.height, u.pad_top, u.pad_bottom), (20, 8, 4, 4));
+        assert_eq!(u.stride, u.pad_left + 20 + u.pad_right);
+        assert_eq!(
+            luma.to_string(),
+            "40x16, stride 96, padding left 32 right 24 top 8 bottom 8, length 3072, origin 800"
+        );
+
+        let layouts = frame.with_alpha(10).layout_report();
+        assert_eq!(layouts.len(), 4);
+        assert_eq!(layouts[3], luma);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn expect_info() {
+        let frame = Frame::<u8>::new_with_padding(64, 32, ChromaSampling::Cs420, 8);
+        let info = frame.info(8, ChromaSamplePosition::Left).unwrap();
+        assert!(frame.matches_info(&info));
+        assert_eq!(frame.expect_info(&info), Ok(()));
+        assert!(frame.matches_info(&FrameInfo {
+            chroma_sample_position: ChromaSamplePosition::Center,
+            ..info
+        }));
+
+        let tests = [
+            (
+                FrameInfo { width: 32, ..info },
+                Error::DimensionMismatch {
+                    expected: (32, 32),
+                    found: (64, 32),
+                },
+            ),
+            (
+                FrameInfo {
+                    chroma_sampling: ChromaSampling::Cs444,
+                    ..info
+                },
+                Error::InvalidChromaSampling,
+            ),
+            (
+                FrameInfo {
+                    bit_depth: 10,
+                    ..info
+                },
+                Error::UnsupportedBitDepth { bit_depth: 10 },
+            ),
+        ];
+
+        for (info, error) in tests {
+            assert!(!frame.matches_info(&info));
+            assert_eq!(frame.expect_info(&info), Err(error));
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn yuv_samples() {
+        let frame = Frame::<u8>::new_from_fn(8, 8, ChromaSampling::Cs420, 4, |i, x, y| {
+            (i * 100 + y * 10 + x) as u8
+        });
+        let samples: Vec<_> = frame.yuv_samples(8).collect();
+        assert_eq!(samples.len(), 64);
+        assert_eq!(samples[0], (0, 0, 0, 100, 200));
+        // (3, 1) shares the chroma sample at (1, 0)
+        assert_eq!(samples[11], (3, 1, 13, 101, 201));
+        assert_eq!(samples[63], (7, 7, 77, 133, 233));
+
+        let mono = Frame::<u16>::new_filled(8, 8, ChromaSampling::Cs400, 0, [64, 0, 0]);
+        assert!(mono
+            .yuv_samples(10)
+            .all(|(_, _, y, u, v)| (y, u, v) == (64, 512, 512)));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn copy_from() {
+        let src = Frame::<u16>::new_from_fn(16, 8, ChromaSampling::Cs420, 0, |i, x, y| {
+            (i * 100 + y * 16 + x) as u16
+        });
+        let mut dst = Frame::new_with_padding(16, 8, ChromaSampling::Cs420, 8);
+        dst.copy_from(&src).unwrap();
+        for (dst, src) in dst.planes.iter().zip(&src.planes) {
+            assert_eq!(dst.to_vec(), src.to_vec());
+        }
+
+        let mut other = Frame::new_filled(16, 8, ChromaSampling::Cs444, 0, [1, 2, 3]);
+        assert_eq!(other.copy_from(&src), Err(Error::InvalidChromaSampling));
+        let mut other = Frame::new_filled(16, 16, ChromaSampling::Cs420, 0, [1, 2, 3]);
+        assert_eq!(
+            other.copy_from(&src),
+            Err(Error::DimensionMismatch {
+                expected: (16, 16),
+                found: (16, 8)
+            })
+        );
+        assert!(other.planes[0].iter().all(|p| p == 1));
+
+        let mut dst = dst.with_alpha(10);
+        dst.alpha_mut().unwrap().fill(7);
+        assert_eq!(dst.copy_from(&src), Err(Error::InconsistentFrame));
+        let mut src = src.with_alpha(10);
+        src.alpha_mut()
+            .unwrap()
+            .fill_with(|x, y| (y * 16 + x) as u16);
+        dst.copy_from(&src).unwrap();
+        assert_eq!(dst.alpha().unwrap().to_vec(), src.alpha().unwrap().to_vec());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn set_subsampling() {
+        let mut frame = Frame::<u8>::new_with_padding(16, 8, ChromaSampling::Cs420, 0);
+        assert_eq!(
+            frame.set_subsampling(ChromaSampling::Cs444),
+            Err(Error::DimensionMismatch {
+                expected: (16, 8),
+                found: (8, 4)
+            })
+        );
+        assert_eq!(frame.chroma_sampling(), Ok(ChromaSampling::Cs420));
+
+        frame.set_subsampling_unchecked(ChromaSampling::Cs444);
+        assert_eq!(frame.chroma_sampling(), Ok(ChromaSampling::Cs444));
+        assert_eq!(frame.validate(), Err(Error::InconsistentFrame));
+
+        frame.set_subsampling(ChromaSampling::Cs420).unwrap();
+        assert_eq!(frame.chroma_sampling(), Ok(ChromaSampling::Cs420));
+        frame.validate().unwrap();
     }
 }
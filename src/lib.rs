@@ -7,6 +7,7 @@
 // Media Patent License 1.0 was not distributed with this source code in the
 // PATENTS file, you can obtain it at www.aomedia.org/license/patent.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 // Safety lints
 #![deny(bare_trait_objects)]
 #![deny(clippy::as_ptr_cast_mut)]
@@ -38,7 +39,7 @@
 #![deny(clippy::mem_forget)]
 #![deny(clippy::path_buf_push_overwrite)]
 #![deny(clippy::same_functions_in_if_condition)]
-#![deny(clippy::unchecked_duration_subtraction)]
+#![deny(clippy::unchecked_time_subtraction)]
 #![deny(clippy::unicode_not_nfc)]
 // Clarity/formatting lints
 #![warn(clippy::checked_conversions)]
@@ -78,6 +79,9 @@
 #![warn(clippy::missing_errors_doc)]
 #![warn(clippy::missing_panics_doc)]
 
+extern crate alloc;
+
+pub mod error;
 pub mod frame;
 pub mod math;
 pub mod pixel;
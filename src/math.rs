@@ -7,8 +7,8 @@
 // Media Patent License 1.0 was not distributed with this source code in the
 // PATENTS file, you can obtain it at www.aomedia.org/license/patent.
 
+use core::mem::size_of;
 use num_traits::PrimInt;
-use std::mem::size_of;
 
 pub trait Fixed {
     fn floor_log2(&self, n: usize) -> usize;
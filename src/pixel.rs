@@ -15,10 +15,13 @@ use wasm_bindgen::prelude::*;
 
 use num_traits::{AsPrimitive, FromPrimitive, PrimInt, Signed};
 
-use std::fmt;
-use std::fmt::{Debug, Display};
-use std::mem::size_of;
-use std::ops::AddAssign;
+use core::fmt;
+use core::fmt::{Debug, Display};
+use core::mem::size_of;
+use core::ops::AddAssign;
+use core::str::FromStr;
+
+use crate::error::Error;
 
 /// Trait for casting between primitive types.
 pub trait CastFromPrimitive<T>: Copy + 'static {
@@ -56,6 +59,7 @@ pub trait RegisteredPrimitive:
     + AsPrimitive<u16>
     + AsPrimitive<i32>
     + AsPrimitive<u32>
+    + AsPrimitive<u64>
     + AsPrimitive<usize>
     + CastFromPrimitive<u8>
     + CastFromPrimitive<i16>
@@ -87,6 +91,7 @@ impl_cast_from_pixel_to_primitive!(i16);
 impl_cast_from_pixel_to_primitive!(u16);
 impl_cast_from_pixel_to_primitive!(i32);
 impl_cast_from_pixel_to_primitive!(u32);
+impl_cast_from_pixel_to_primitive!(u64);
 
 /// Types that can be used as pixel types.
 #[derive(PartialEq, Eq)]
@@ -103,6 +108,9 @@ pub trait Pixel:
 {
     type Coeff: Coefficient;
 
+    /// Size of the pixel type in bytes.
+    const BYTES: usize = size_of::<Self>();
+
     /// Returns a [`PixelType`] variant corresponding to this type.
     ///
     /// [`PixelType`]: enum.PixelType.html
@@ -113,6 +121,34 @@ pub trait Pixel:
     fn to_asm_stride(in_stride: usize) -> isize {
         (in_stride * size_of::<Self>()) as isize
     }
+
+    /// Returns the largest value representable with `bit_depth` bits, capped
+    /// to the largest value of the type.
+    #[inline]
+    fn max_for_depth(bit_depth: usize) -> Self {
+        let max = u32::try_from(bit_depth)
+            .ok()
+            .and_then(|bit_depth| 1u32.checked_shl(bit_depth))
+            .map_or(u32::MAX, |range| range - 1);
+        Self::cast_from(max.min(u32::cast_from(Self::max_value())))
+    }
+
+    /// Returns the neutral value for `bit_depth` bits, i.e. the midpoint of
+    /// the range, as used for chroma without color.
+    ///
+    /// Like [`Pixel::max_for_depth`], the range is capped to that of the type,
+    /// and is empty for a bit depth of zero.
+    #[inline]
+    fn neutral_for_depth(bit_depth: usize) -> Self {
+        let max = u32::cast_from(Self::max_for_depth(bit_depth));
+        Self::cast_from((max + 1) / 2)
+    }
+
+    /// Clamps the value to the range representable with `bit_depth` bits.
+    #[inline]
+    fn clamp_to_depth(self, bit_depth: usize) -> Self {
+        self.min(Self::max_for_depth(bit_depth))
+    }
 }
 
 impl Pixel for u8 {
@@ -147,7 +183,7 @@ impl Coefficient for i32 {
 }
 
 /// Chroma subsampling format
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen)]
 #[repr(C)]
@@ -163,6 +199,20 @@ pub enum ChromaSampling {
     Cs400,
 }
 
+/// Position of the subsampled chroma samples relative to the luma samples.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ChromaSamplePosition {
+    /// Horizontally co-sited with the left luma sample and vertically
+    /// between the luma samples, as in MPEG-2 and H.264 by default.
+    #[default]
+    Left,
+    /// Centered between the luma samples, as in JPEG.
+    Center,
+    /// Co-sited with the top-left luma sample.
+    TopLeft,
+}
+
 impl FromPrimitive for ChromaSampling {
     fn from_i64(n: i64) -> Option<Self> {
         use ChromaSampling::*;
@@ -181,6 +231,15 @@ impl FromPrimitive for ChromaSampling {
     }
 }
 
+/// Converts a numeric tag as returned by [`ChromaSampling::as_u8`].
+impl TryFrom<u8> for ChromaSampling {
+    type Error = Error;
+
+    fn try_from(tag: u8) -> Result<Self, Self::Error> {
+        ChromaSampling::from_u8(tag).ok_or(Error::InvalidChromaSampling)
+    }
+}
+
 impl fmt::Display for ChromaSampling {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(
@@ -196,6 +255,25 @@ impl fmt::Display for ChromaSampling {
     }
 }
 
+impl FromStr for ChromaSampling {
+    type Err = Error;
+
+    /// Parses the names produced by the `Display` implementation, as well as
+    /// the variant names and their `Yuv` aliases (e.g. `Cs420` or `Yuv420`).
+    /// Matching is case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use ChromaSampling::*;
+
+        match s.to_ascii_lowercase().as_str() {
+            "4:2:0" | "cs420" | "yuv420" => Ok(Cs420),
+            "4:2:2" | "cs422" | "yuv422" => Ok(Cs422),
+            "4:4:4" | "cs444" | "yuv444" => Ok(Cs444),
+            "monochrome" | "4:0:0" | "cs400" | "yuv400" => Ok(Cs400),
+            _ => Err(Error::InvalidChromaSampling),
+        }
+    }
+}
+
 impl ChromaSampling {
     /// Provides the amount to right shift the luma plane dimensions to get the
     ///  chroma plane dimensions.
@@ -213,6 +291,40 @@ impl ChromaSampling {
         }
     }
 
+    /// Returns the numeric tag of the chroma sampling, for embedding it in
+    /// binary formats.
+    ///
+    /// The mapping is stable and matches the discriminants: 0 for 4:2:0, 1 for
+    /// 4:2:2, 2 for 4:4:4 and 3 for monochrome.
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the chroma sampling of a planar YUV four character code
+    /// (e.g. `I420`), or `None` if the code is not recognized.
+    pub const fn from_fourcc(code: [u8; 4]) -> Option<Self> {
+        use self::ChromaSampling::*;
+        match &code {
+            b"I420" => Some(Cs420),
+            b"I422" => Some(Cs422),
+            b"I444" => Some(Cs444),
+            b"Y800" | b"GREY" => Some(Cs400),
+            _ => None,
+        }
+    }
+
+    /// Returns the four character code of the 8-bit planar YUV format with this
+    /// chroma sampling.
+    pub const fn to_fourcc(self) -> [u8; 4] {
+        use self::ChromaSampling::*;
+        match self {
+            Cs420 => *b"I420",
+            Cs422 => *b"I422",
+            Cs444 => *b"I444",
+            Cs400 => *b"Y800",
+        }
+    }
+
     /// Calculates the size of a chroma plane for this sampling type, given the luma plane dimensions.
     pub const fn get_chroma_dimensions(
         self,
@@ -225,6 +337,42 @@ impl ChromaSampling {
             (0, 0)
         }
     }
+
+    /// Returns the horizontal and vertical offsets, in luma samples, of the
+    /// first chroma sample from the first luma sample for the given sample
+    /// position.
+    ///
+    /// Upsampling filters use these as the phase of their taps. Directions
+    /// without subsampling always have an offset of 0.
+    pub const fn chroma_phase(self, pos: ChromaSamplePosition) -> (f32, f32) {
+        use self::ChromaSamplePosition::*;
+        use self::ChromaSampling::*;
+        match (self, pos) {
+            (Cs420, Left) => (0.0, 0.5),
+            (Cs420, Center) => (0.5, 0.5),
+            (Cs422, Center) => (0.5, 0.0),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Returns the size in bytes of a tightly packed planar frame with this
+    /// sampling type, given the luma plane dimensions and the bit depth.
+    ///
+    /// Samples take one byte for bit depths up to 8 and two bytes otherwise,
+    /// as in raw YUV files.
+    /// Returns `None` if the bit depth is not between 1 and 16 or the size
+    /// overflows.
+    pub fn frame_byte_size(self, width: usize, height: usize, bit_depth: usize) -> Option<usize> {
+        if !(1..=16).contains(&bit_depth) {
+            return None;
+        }
+        let bytes = if bit_depth > 8 { 2 } else { 1 };
+        let (chroma_width, chroma_height) = self.get_chroma_dimensions(width, height);
+
+        let luma = width.checked_mul(height)?;
+        let chroma = chroma_width.checked_mul(chroma_height)?.checked_mul(2)?;
+        luma.checked_add(chroma)?.checked_mul(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -248,6 +396,48 @@ mod test {
         }
     }
 
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn depth_values() {
+        assert_eq!(u8::BYTES, 1);
+        assert_eq!(u16::BYTES, 2);
+
+        let tests = [
+            (8, 255, 128),
+            (10, 1023, 512),
+            (12, 4095, 2048),
+            (16, 65535, 32768),
+        ];
+        for (bit_depth, max, neutral) in tests {
+            assert_eq!(u16::max_for_depth(bit_depth), max);
+            assert_eq!(u16::neutral_for_depth(bit_depth), neutral);
+        }
+
+        assert_eq!(u8::max_for_depth(8), 255);
+        assert_eq!(u8::max_for_depth(6), 63);
+        assert_eq!(u8::neutral_for_depth(8), 128);
+        assert_eq!(u8::max_for_depth(10), 255);
+        assert_eq!(u8::neutral_for_depth(10), 128);
+
+        assert_eq!(u16::max_for_depth(0), 0);
+        assert_eq!(u16::neutral_for_depth(0), 0);
+        assert_eq!(u16::max_for_depth(40), 65535);
+        assert_eq!(u16::neutral_for_depth(40), 32768);
+        assert_eq!(u16::max_for_depth(usize::MAX), 65535);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn clamp_to_depth() {
+        assert_eq!(255u8.clamp_to_depth(8), 255);
+        assert_eq!(200u8.clamp_to_depth(7), 127);
+        assert_eq!(1023u16.clamp_to_depth(10), 1023);
+        assert_eq!(1024u16.clamp_to_depth(10), 1023);
+        assert_eq!(4000u16.clamp_to_depth(12), 4000);
+        assert_eq!(u16::MAX.clamp_to_depth(12), 4095);
+        assert_eq!(u16::MAX.clamp_to_depth(16), u16::MAX);
+    }
+
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
     fn type_enum() {
@@ -276,6 +466,29 @@ mod test {
         }
     }
 
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn chroma_sampling_tag() {
+        let tests = [
+            (0, ChromaSampling::Cs420),
+            (1, ChromaSampling::Cs422),
+            (2, ChromaSampling::Cs444),
+            (3, ChromaSampling::Cs400),
+        ];
+
+        for (tag, cs) in tests {
+            assert_eq!(cs.as_u8(), tag);
+            assert_eq!(ChromaSampling::try_from(tag), Ok(cs));
+        }
+
+        for tag in [4, 255] {
+            assert_eq!(
+                ChromaSampling::try_from(tag),
+                Err(Error::InvalidChromaSampling)
+            );
+        }
+    }
+
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
     fn display_chroma_sampling() {
@@ -313,4 +526,104 @@ mod test {
             assert_eq!(chroma, expected_chroma);
         }
     }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn chroma_phase() {
+        use ChromaSamplePosition::*;
+
+        let tests = [
+            (ChromaSampling::Cs420, Left, (0.0, 0.5)),
+            (ChromaSampling::Cs420, Center, (0.5, 0.5)),
+            (ChromaSampling::Cs420, TopLeft, (0.0, 0.0)),
+            (ChromaSampling::Cs422, Left, (0.0, 0.0)),
+            (ChromaSampling::Cs422, Center, (0.5, 0.0)),
+            (ChromaSampling::Cs444, Center, (0.0, 0.0)),
+            (ChromaSampling::Cs400, Left, (0.0, 0.0)),
+        ];
+
+        for (cs, pos, expected) in tests {
+            let (x, y) = cs.chroma_phase(pos);
+            assert!((x - expected.0).abs() < f32::EPSILON);
+            assert!((y - expected.1).abs() < f32::EPSILON);
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn frame_byte_size() {
+        let tests = [
+            (ChromaSampling::Cs420, 8, Some(64 * 48 * 3 / 2)),
+            (ChromaSampling::Cs420, 10, Some(64 * 48 * 3)),
+            (ChromaSampling::Cs422, 8, Some(64 * 48 * 2)),
+            (ChromaSampling::Cs444, 12, Some(64 * 48 * 6)),
+            (ChromaSampling::Cs400, 8, Some(64 * 48)),
+            (ChromaSampling::Cs420, 0, None),
+            (ChromaSampling::Cs420, 17, None),
+        ];
+
+        for (cs, bit_depth, expected) in tests {
+            assert_eq!(cs.frame_byte_size(64, 48, bit_depth), expected);
+        }
+
+        // Odd dimensions round the chroma planes up
+        assert_eq!(
+            ChromaSampling::Cs420.frame_byte_size(5, 3, 8),
+            Some(5 * 3 + 3 * 2 * 2)
+        );
+        assert_eq!(
+            ChromaSampling::Cs444.frame_byte_size(usize::MAX, 2, 8),
+            None
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn parse_chroma_sampling() {
+        let all_cs = [
+            ChromaSampling::Cs420,
+            ChromaSampling::Cs422,
+            ChromaSampling::Cs444,
+            ChromaSampling::Cs400,
+        ];
+
+        for cs in all_cs {
+            assert_eq!(cs.to_string().parse(), Ok(cs));
+            assert_eq!(format!("{cs:?}").parse(), Ok(cs));
+        }
+
+        assert_eq!("Yuv420".parse(), Ok(ChromaSampling::Cs420));
+        assert_eq!("yuv444".parse(), Ok(ChromaSampling::Cs444));
+        assert_eq!(
+            "4:1:1".parse::<ChromaSampling>(),
+            Err(Error::InvalidChromaSampling)
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn chroma_sampling_fourcc() {
+        let tests = [
+            (*b"I420", Some(ChromaSampling::Cs420)),
+            (*b"I422", Some(ChromaSampling::Cs422)),
+            (*b"I444", Some(ChromaSampling::Cs444)),
+            (*b"Y800", Some(ChromaSampling::Cs400)),
+            (*b"GREY", Some(ChromaSampling::Cs400)),
+            (*b"YV12", None),
+            (*b"i420", None),
+        ];
+
+        for (fourcc, expected) in tests {
+            assert_eq!(ChromaSampling::from_fourcc(fourcc), expected);
+        }
+
+        for cs in [
+            ChromaSampling::Cs420,
+            ChromaSampling::Cs422,
+            ChromaSampling::Cs444,
+            ChromaSampling::Cs400,
+        ] {
+            assert_eq!(ChromaSampling::from_fourcc(cs.to_fourcc()), Some(cs));
+        }
+    }
 }
@@ -7,22 +7,28 @@
 // Media Patent License 1.0 was not distributed with this source code in the
 // PATENTS file, you can obtain it at www.aomedia.org/license/patent.
 
-use std::fmt::{Debug, Display, Formatter};
-use std::iter::{self, FusedIterator};
-use std::marker::PhantomData;
-use std::mem::size_of;
-use std::ops::{Index, IndexMut, Range};
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::iter::{self, FusedIterator};
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::num::NonZeroUsize;
+use core::ops::{Index, IndexMut, Range};
+use core::slice::{ChunksExact, ChunksExactMut};
+
+use alloc::vec;
+use alloc::vec::Vec;
 
 use aligned_vec::{ABox, AVec, ConstAlign};
 
-use crate::math::*;
+use crate::error::Error;
 use crate::pixel::*;
 
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 
 /// Plane-specific configuration.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct PlaneConfig {
     /// Data stride.
@@ -55,6 +61,9 @@ impl PlaneConfig {
     /// Stride alignment in bytes.
     const STRIDE_ALIGNMENT_LOG2: usize = 6;
 
+    /// # Panics
+    ///
+    /// - If the size of the plane overflows, see [`PlaneConfig::try_new`]
     #[inline]
     pub fn new(
         width: usize,
@@ -65,13 +74,47 @@ impl PlaneConfig {
         ypad: usize,
         type_size: usize,
     ) -> Self {
-        let xorigin = xpad.align_power_of_two(Self::STRIDE_ALIGNMENT_LOG2 + 1 - type_size);
-        let yorigin = ypad;
-        let stride = (xorigin + width + xpad)
-            .align_power_of_two(Self::STRIDE_ALIGNMENT_LOG2 + 1 - type_size);
-        let alloc_height = yorigin + height + ypad;
+        Self::try_new(width, height, xdec, ydec, xpad, ypad, type_size)
+            .expect("plane dimensions overflow")
+    }
+
+    /// Same as [`PlaneConfig::new`], but with checked arithmetic.
+    ///
+    /// # Errors
+    ///
+    /// - If the stride, the allocated height or the size in bytes of the
+    ///   plane overflows, or the size exceeds `isize::MAX` bytes
+    pub fn try_new(
+        width: usize,
+        height: usize,
+        xdec: usize,
+        ydec: usize,
+        xpad: usize,
+        ypad: usize,
+        type_size: usize,
+    ) -> Result<Self, Error> {
+        let mask = (1 << (Self::STRIDE_ALIGNMENT_LOG2 + 1 - type_size)) - 1;
+        let align = |v: usize| v.checked_add(mask).map(|v| v & !mask);
 
-        PlaneConfig {
+        let too_large = Error::AllocationTooLarge { width, height };
+        let xorigin = align(xpad).ok_or(too_large)?;
+        let yorigin = ypad;
+        let stride = xorigin
+            .checked_add(width)
+            .and_then(|v| v.checked_add(xpad))
+            .and_then(align)
+            .ok_or(too_large)?;
+        let alloc_height = yorigin
+            .checked_add(height)
+            .and_then(|v| v.checked_add(ypad))
+            .ok_or(too_large)?;
+        stride
+            .checked_mul(alloc_height)
+            .and_then(|v| v.checked_mul(type_size))
+            .filter(|&bytes| isize::try_from(bytes).is_ok())
+            .ok_or(too_large)?;
+
+        Ok(PlaneConfig {
             stride,
             alloc_height,
             width,
@@ -82,8 +125,142 @@ impl PlaneConfig {
             ypad,
             xorigin,
             yorigin,
+        })
+    }
+
+    /// Checks that the configuration is consistent, as produced by
+    /// [`PlaneConfig::new`]: the padding fits around the visible area within
+    /// the stride and allocated height, and the decimation is at most 1.
+    ///
+    /// This is useful for configurations built field by field.
+    ///
+    /// # Errors
+    ///
+    /// - If the padding, origin and visible area do not fit in the stride or
+    ///   the allocated height
+    /// - If the decimation is larger than 1
+    pub fn validate(&self) -> Result<(), Error> {
+        let fits = |origin: usize, pad: usize, len: usize, total: usize| {
+            origin >= pad
+                && origin
+                    .checked_add(len)
+                    .and_then(|end| end.checked_add(pad))
+                    .map_or(false, |end| end <= total)
+        };
+
+        if !fits(self.xorigin, self.xpad, self.width, self.stride)
+            || !fits(self.yorigin, self.ypad, self.height, self.alloc_height)
+            || self.xdec > 1
+            || self.ydec > 1
+        {
+            return Err(Error::InvalidPlaneConfig);
+        }
+
+        Ok(())
+    }
+}
+
+/// Byte order of multi-byte samples in raw pixel data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first.
+    #[default]
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+/// How the padding of a plane is filled by [`Plane::with_padding`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaddingFill<T: Pixel> {
+    /// Replicate the edge pixels, as done by [`Plane::pad`].
+    Replicate,
+    /// Fill with a constant value, as done by [`Plane::pad_constant`].
+    Constant(T),
+}
+
+/// Interpolation filter used when resizing a plane.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Nearest neighbor.
+    Nearest,
+    /// Bilinear interpolation.
+    #[default]
+    Bilinear,
+    /// Lanczos resampling with 3 lobes.
+    Lanczos3,
+}
+
+#[cfg(feature = "std")]
+impl ResizeFilter {
+    /// Returns the weight of a source sample at distance `x` from the
+    /// sampled position.
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            ResizeFilter::Nearest => f32::from(u8::from(x < 0.5)),
+            ResizeFilter::Bilinear => (1.0 - x).max(0.0),
+            ResizeFilter::Lanczos3 => {
+                if x < f32::EPSILON {
+                    1.0
+                } else if x < 3.0 {
+                    let pi_x = core::f32::consts::PI * x;
+                    3.0 * pi_x.sin() * (pi_x / 3.0).sin() / (pi_x * pi_x)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Returns the distance from the sampled position past which source
+    /// samples have no weight.
+    const fn radius(self) -> f32 {
+        match self {
+            ResizeFilter::Nearest => 0.5,
+            ResizeFilter::Bilinear => 1.0,
+            ResizeFilter::Lanczos3 => 3.0,
         }
     }
+
+    /// Returns, for each of the `dst_len` output samples, the index of the
+    /// first contributing input sample and the normalized weights of the
+    /// contributing input samples.
+    fn contributions(self, src_len: usize, dst_len: usize) -> Vec<(usize, Vec<f32>)> {
+        let scale = src_len as f32 / dst_len as f32;
+
+        if self == ResizeFilter::Nearest {
+            return (0..dst_len)
+                .map(|i| {
+                    let src = ((i as f32 + 0.5) * scale) as usize;
+                    (src.min(src_len - 1), vec![1.0])
+                })
+                .collect();
+        }
+
+        // Widen the filter when downscaling to avoid aliasing
+        let filter_scale = scale.max(1.0);
+        let support = self.radius() * filter_scale;
+
+        (0..dst_len)
+            .map(|i| {
+                let center = (i as f32 + 0.5) * scale;
+                let start = (center - support).floor().max(0.0) as usize;
+                let end = ((center + support).ceil() as usize).min(src_len);
+
+                let mut weights: Vec<f32> = (start..end)
+                    .map(|j| self.weight((j as f32 + 0.5 - center) / filter_scale))
+                    .collect();
+                let total: f32 = weights.iter().sum();
+                for weight in &mut weights {
+                    *weight /= total;
+                }
+
+                (start, weights)
+            })
+            .collect()
+    }
 }
 
 /// Absolute offset in pixels inside a plane
@@ -93,6 +270,56 @@ pub struct PlaneOffset {
     pub y: isize,
 }
 
+/// Memory layout of a plane, as returned by [`Plane::layout`].
+///
+/// All values are in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlaneLayout {
+    /// Width of the visible area.
+    pub width: usize,
+    /// Height of the visible area.
+    pub height: usize,
+    /// Distance between the starts of consecutive rows.
+    pub stride: usize,
+    /// Columns left of the visible area.
+    pub pad_left: usize,
+    /// Columns right of the visible area, including the alignment slack.
+    pub pad_right: usize,
+    /// Rows above the visible area.
+    pub pad_top: usize,
+    /// Rows below the visible area.
+    pub pad_bottom: usize,
+    /// Length of the buffer.
+    pub len: usize,
+    /// Offset of the first visible pixel in the buffer.
+    pub origin: usize,
+}
+
+impl Display for PlaneLayout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}x{}, stride {}, padding left {} right {} top {} bottom {}, length {}, origin {}",
+            self.width,
+            self.height,
+            self.stride,
+            self.pad_left,
+            self.pad_right,
+            self.pad_top,
+            self.pad_bottom,
+            self.len,
+            self.origin
+        )
+    }
+}
+
+/// Aligned allocation backing the Plane data
+#[cfg(not(target_arch = "wasm32"))]
+pub type AlignedBox<T> = ABox<[T], ConstAlign<{ 1 << 6 }>>;
+/// Aligned allocation backing the Plane data
+#[cfg(target_arch = "wasm32")]
+pub type AlignedBox<T> = ABox<[T], ConstAlign<{ 1 << 3 }>>;
+
 /// Backing buffer for the Plane data
 ///
 /// The buffer is padded and aligned according to the architecture-specific
@@ -100,16 +327,13 @@ pub struct PlaneOffset {
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct PlaneData<T: Pixel> {
-    #[cfg(not(target_arch = "wasm32"))]
-    data: ABox<[T], ConstAlign<{ 1 << 6 }>>,
-    #[cfg(target_arch = "wasm32")]
-    data: ABox<[T], ConstAlign<{ 1 << 3 }>>,
+    data: AlignedBox<T>,
 }
 
 unsafe impl<T: Pixel + Send> Send for PlaneData<T> {}
 unsafe impl<T: Pixel + Sync> Sync for PlaneData<T> {}
 
-impl<T: Pixel> std::ops::Deref for PlaneData<T> {
+impl<T: Pixel> core::ops::Deref for PlaneData<T> {
     type Target = [T];
 
     fn deref(&self) -> &[T] {
@@ -117,7 +341,7 @@ impl<T: Pixel> std::ops::Deref for PlaneData<T> {
     }
 }
 
-impl<T: Pixel> std::ops::DerefMut for PlaneData<T> {
+impl<T: Pixel> core::ops::DerefMut for PlaneData<T> {
     fn deref_mut(&mut self) -> &mut [T] {
         self.data.as_mut()
     }
@@ -131,12 +355,14 @@ impl<T: Pixel> PlaneData<T> {
     const DATA_ALIGNMENT: usize = 1 << 6;
 
     pub fn new(len: usize) -> Self {
+        Self::new_filled(len, T::cast_from(128))
+    }
+
+    /// Allocates a buffer of `len` pixels set to `value`.
+    pub fn new_filled(len: usize, value: T) -> Self {
         Self {
-            data: AVec::from_iter(
-                Self::DATA_ALIGNMENT,
-                iter::repeat(T::cast_from(128)).take(len),
-            )
-            .into_boxed_slice(),
+            data: AVec::from_iter(Self::DATA_ALIGNMENT, iter::repeat(value).take(len))
+                .into_boxed_slice(),
         }
     }
 
@@ -150,6 +376,10 @@ impl<T: Pixel> PlaneData<T> {
 /// One data plane of a frame.
 ///
 /// For example, a plane can be a Y luma plane or a U or V chroma plane.
+///
+/// The plane does not record the bit depth of its samples. Methods that
+/// depend on it, such as [`Plane::clamp_to_depth`], take it as an argument,
+/// so the planes of a frame may be processed with independent bit depths.
 #[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Plane<T: Pixel> {
@@ -165,7 +395,7 @@ impl<T: Pixel> Debug for Plane<T>
 where
     T: Display,
 {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "Plane {{ data: [{}, ...], cfg: {:?} }}",
@@ -174,6 +404,22 @@ where
     }
 }
 
+/// Hashes the dimensions and the visible pixels of the plane, so that planes
+/// holding the same picture with a different padding hash the same.
+///
+/// This is O(pixels) and may be expensive for large planes.
+impl<T: Pixel + Hash> Hash for Plane<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cfg.width.hash(state);
+        self.cfg.height.hash(state);
+        self.cfg.xdec.hash(state);
+        self.cfg.ydec.hash(state);
+        for row in self.rows_iter() {
+            row.hash(state);
+        }
+    }
+}
+
 impl<T: Pixel> Plane<T> {
     /// Allocates and returns a new plane.
     pub fn new(
@@ -185,11 +431,106 @@ impl<T: Pixel> Plane<T> {
         ypad: usize,
     ) -> Self {
         let cfg = PlaneConfig::new(width, height, xdec, ydec, xpad, ypad, size_of::<T>());
-        let data = PlaneData::new(cfg.stride * cfg.alloc_height);
+
+        Self::with_config(cfg)
+    }
+
+    /// Allocates and returns a new standalone plane without padding nor
+    /// decimation, such as an alpha mask or a depth map.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in the pixel type
+    /// - If the size of the plane overflows
+    pub fn try_new(
+        width: NonZeroUsize,
+        height: NonZeroUsize,
+        bit_depth: usize,
+    ) -> Result<Self, Error> {
+        Self::try_new_padded(width, height, 0, 0, bit_depth)
+    }
+
+    /// Allocates and returns a new standalone plane without decimation, with
+    /// the given padding.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in the pixel type
+    /// - If the size of the plane overflows
+    pub fn try_new_padded(
+        width: NonZeroUsize,
+        height: NonZeroUsize,
+        xpad: usize,
+        ypad: usize,
+        bit_depth: usize,
+    ) -> Result<Self, Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
+        }
+
+        let cfg =
+            PlaneConfig::try_new(width.get(), height.get(), 0, 0, xpad, ypad, size_of::<T>())?;
+
+        Ok(Self::with_config(cfg))
+    }
+
+    /// Allocates and returns a new plane with the given configuration.
+    pub(crate) fn with_config(cfg: PlaneConfig) -> Self {
+        Self::with_config_filled(cfg, T::cast_from(128))
+    }
+
+    /// Allocates a plane with the given configuration, with all pixels
+    /// including the padding set to `value`.
+    pub(crate) fn with_config_filled(cfg: PlaneConfig, value: T) -> Self {
+        let data = PlaneData::new_filled(cfg.stride * cfg.alloc_height, value);
 
         Plane { data, cfg }
     }
 
+    /// Reconfigures the plane with the given parameters, as if it had been
+    /// created with [`Plane::new`].
+    ///
+    /// The existing allocation is reused, keeping its contents, when it has
+    /// the size required by the new configuration.
+    pub fn reinit(
+        &mut self,
+        width: usize,
+        height: usize,
+        xdec: usize,
+        ydec: usize,
+        xpad: usize,
+        ypad: usize,
+    ) {
+        let cfg = PlaneConfig::new(width, height, xdec, ydec, xpad, ypad, size_of::<T>());
+
+        self.reinit_with_config(cfg);
+    }
+
+    pub(crate) fn reinit_with_config(&mut self, cfg: PlaneConfig) {
+        let len = cfg.stride * cfg.alloc_height;
+        if self.data.len() != len {
+            self.data = PlaneData::new(len);
+        }
+        self.cfg = cfg;
+    }
+
+    /// Wraps an existing aligned allocation into a plane, without copying.
+    ///
+    /// # Panics
+    ///
+    /// - If the visible area does not fit within `cfg.stride` and `cfg.alloc_height`
+    /// - If `data` is shorter than `cfg.stride * cfg.alloc_height`
+    pub fn from_aligned_box(data: AlignedBox<T>, cfg: PlaneConfig) -> Self {
+        assert!(cfg.xorigin + cfg.width <= cfg.stride);
+        assert!(cfg.yorigin + cfg.height <= cfg.alloc_height);
+        assert!(data.len() >= cfg.stride * cfg.alloc_height);
+
+        Plane {
+            data: PlaneData { data },
+            cfg,
+        }
+    }
+
     /// # Panics
     ///
     /// - If `len` is not a multiple of `stride`
@@ -215,6 +556,40 @@ impl<T: Pixel> Plane<T> {
         }
     }
 
+    /// Creates an unpadded plane from `data`, holding `width * height` pixels
+    /// in row-major order. The pixels are copied into an aligned allocation.
+    ///
+    /// # Errors
+    ///
+    /// - If the length of `data` is not `width * height`
+    pub fn from_vec(data: Vec<T>, width: usize, height: usize) -> Result<Self, Error> {
+        let expected = width
+            .checked_mul(height)
+            .ok_or(Error::AllocationTooLarge { width, height })?;
+        if data.len() != expected {
+            return Err(Error::DataLength {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        Ok(Self {
+            data: PlaneData::from_slice(&data),
+            cfg: PlaneConfig {
+                stride: width,
+                alloc_height: height,
+                width,
+                height,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 0,
+                yorigin: 0,
+            },
+        })
+    }
+
     pub fn pad(&mut self, w: usize, h: usize) {
         let xorigin = self.cfg.xorigin;
         let yorigin = self.cfg.yorigin;
@@ -328,11 +703,65 @@ impl<T: Pixel> Plane<T> {
         base..base + width
     }
 
+    /// Returns the distance in pixels between the starts of two consecutive
+    /// lines of the plane data.
+    ///
+    /// This is the stride to pass along with [`Plane::data_origin`] to APIs
+    /// taking strided buffers. Multiply it by `size_of::<T>()` to get the
+    /// stride in bytes.
+    #[inline]
+    pub const fn stride(&self) -> usize {
+        self.cfg.stride
+    }
+
     /// Returns the pixel at the given coordinates.
+    ///
+    /// Coordinates past the visible area address the padding and only panic
+    /// when they fall outside of the allocated buffer. See
+    /// [`Plane::visible_pixel`] for an accessor restricted to the visible area.
     pub fn p(&self, x: usize, y: usize) -> T {
         self.data[self.index(x, y)]
     }
 
+    /// Returns the pixel at the given coordinates, or `None` if they are
+    /// outside of the visible area.
+    pub fn visible_pixel(&self, x: usize, y: usize) -> Option<T> {
+        if x < self.cfg.width && y < self.cfg.height {
+            Some(self.p(x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the pixel at the given coordinates without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `(y + yorigin) * stride + x + xorigin` is
+    /// within the allocated buffer, padding included. Bounds are only checked
+    /// in debug builds.
+    #[inline]
+    pub unsafe fn pixel_unchecked(&self, x: usize, y: usize) -> T {
+        let index = self.index(x, y);
+        debug_assert!(index < self.data.len());
+        *self.data.get_unchecked(index)
+    }
+
+    /// Returns a mutable reference to the pixel at the given coordinates
+    /// without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `(y + yorigin) * stride + x + xorigin` is
+    /// within the allocated buffer, padding included. Bounds are only checked
+    /// in debug builds.
+    #[inline]
+    pub unsafe fn pixel_unchecked_mut(&mut self, x: usize, y: usize) -> &mut T {
+        let index = self.index(x, y);
+        debug_assert!(index < self.data.len());
+        self.data.get_unchecked_mut(index)
+    }
+
     /// Returns plane data starting from the origin.
     pub fn data_origin(&self) -> &[T] {
         &self.data[self.index(0, 0)..]
@@ -346,6 +775,8 @@ impl<T: Pixel> Plane<T> {
 
     /// Copies data into the plane from a pixel array.
     ///
+    /// Samples wider than a byte are read as little-endian.
+    ///
     /// # Panics
     ///
     /// - If `source_bytewidth` does not match the generic `T` of `Plane`
@@ -354,6 +785,63 @@ impl<T: Pixel> Plane<T> {
         source: &[u8],
         source_stride: usize,
         source_bytewidth: usize,
+    ) {
+        self.copy_from_raw_u8_with_endianness(
+            source,
+            source_stride,
+            source_bytewidth,
+            Endianness::Little,
+        );
+    }
+
+    /// Same as [`Plane::copy_from_raw_u8`] with samples of the width of `T`,
+    /// but checks the stride and the length of `source` first.
+    ///
+    /// `byte_stride` is in bytes, as reported by most decoders, and does not
+    /// need to be a multiple of the sample size. `source` must hold at least
+    /// `byte_stride * height` bytes.
+    ///
+    /// # Errors
+    ///
+    /// - If `byte_stride` is smaller than a row of the plane in bytes
+    /// - If `source` is too short for the plane
+    pub fn try_copy_from_raw_u8(
+        &mut self,
+        source: &[u8],
+        byte_stride: NonZeroUsize,
+    ) -> Result<(), Error> {
+        let row_len = self.cfg.width * size_of::<T>();
+        if byte_stride.get() < row_len {
+            return Err(Error::InvalidStride {
+                stride: byte_stride.get(),
+                row_len,
+            });
+        }
+        let expected = byte_stride.get() * self.cfg.height;
+        if source.len() < expected {
+            return Err(Error::DataLength {
+                expected,
+                found: source.len(),
+            });
+        }
+
+        self.copy_from_raw_u8(source, byte_stride.get(), size_of::<T>());
+
+        Ok(())
+    }
+
+    /// Copies data into the plane from a pixel array, reading samples wider
+    /// than a byte with the given byte order.
+    ///
+    /// # Panics
+    ///
+    /// - If `source_bytewidth` does not match the generic `T` of `Plane`
+    pub fn copy_from_raw_u8_with_endianness(
+        &mut self,
+        source: &[u8],
+        source_stride: usize,
+        source_bytewidth: usize,
+        endianness: Endianness,
     ) {
         let stride = self.cfg.stride;
 
@@ -381,15 +869,42 @@ impl<T: Pixel> Plane<T> {
                     debug_assert!(T::type_enum() == PixelType::U16);
 
                     // SAFETY: because of the assert it is safe to assume that T == u16
-                    let self_row: &mut [u16] = unsafe { std::mem::transmute(self_row) };
+                    let self_row: &mut [u16] = unsafe { core::mem::transmute(self_row) };
+
+                    if cfg!(target_endian = "little") && endianness == Endianness::Little {
+                        // The samples are stored in the same byte order as the
+                        // source, so the row can be copied in bulk regardless of
+                        // the alignment of the source.
+                        let len = self_row.len().min(source_row.len() / 2);
+                        // SAFETY: we reinterpret the samples as bytes, which is
+                        // valid since any byte pattern is a valid u16
+                        let self_bytes: &mut [u8] = unsafe {
+                            core::slice::from_raw_parts_mut(self_row.as_mut_ptr().cast(), len * 2)
+                        };
+                        self_bytes.copy_from_slice(&source_row[..len * 2]);
+                        continue;
+                    }
+
                     // SAFETY: we reinterpret the slice of bytes as a slice of elements of
                     // [u8; 2] to allow for more efficient codegen with from_le_bytes
                     let source_row: &[[u8; 2]] = unsafe {
-                        std::slice::from_raw_parts(source_row.as_ptr().cast(), source_row.len() / 2)
+                        core::slice::from_raw_parts(
+                            source_row.as_ptr().cast(),
+                            source_row.len() / 2,
+                        )
                     };
 
-                    for (self_pixel, bytes) in self_row.iter_mut().zip(source_row) {
-                        *self_pixel = u16::from_le_bytes(*bytes);
+                    match endianness {
+                        Endianness::Little => {
+                            for (self_pixel, bytes) in self_row.iter_mut().zip(source_row) {
+                                *self_pixel = u16::from_le_bytes(*bytes);
+                            }
+                        }
+                        Endianness::Big => {
+                            for (self_pixel, bytes) in self_row.iter_mut().zip(source_row) {
+                                *self_pixel = u16::from_be_bytes(*bytes);
+                            }
+                        }
                     }
                 }
 
@@ -428,7 +943,7 @@ impl<T: Pixel> Plane<T> {
                     // SAFETY: we reinterpret the slice of bytes as a slice
                     // of [u8; 2] with half the elements
                     let dest_row: &mut [[u8; 2]] = unsafe {
-                        std::slice::from_raw_parts_mut(
+                        core::slice::from_raw_parts_mut(
                             dest_row.as_mut_ptr().cast(),
                             dest_row.len() / 2,
                         )
@@ -497,6 +1012,37 @@ impl<T: Pixel> Plane<T> {
         new
     }
 
+    /// Returns a plane with half the resolution for width and height (not
+    /// padded), averaging each 2x2 block with rounding to nearest.
+    ///
+    /// The last column and row of planes with odd dimensions are dropped.
+    pub fn downsample_2x(&self) -> Plane<T> {
+        let width = self.cfg.width / 2;
+        let height = self.cfg.height / 2;
+        let mut new = Plane::new(width, height, self.cfg.xdec + 1, self.cfg.ydec + 1, 0, 0);
+
+        let stride = self.cfg.stride;
+        let data_origin = self.data_origin();
+        for (row_idx, dst_row) in new.rows_iter_mut().enumerate() {
+            let src_top_row = &data_origin[(stride * row_idx * 2)..][..(2 * width)];
+            let src_bottom_row = &data_origin[(stride * (row_idx * 2 + 1))..][..(2 * width)];
+
+            for ((dst, a), b) in dst_row
+                .iter_mut()
+                .zip(src_top_row.chunks_exact(2))
+                .zip(src_bottom_row.chunks_exact(2))
+            {
+                let sum = u32::cast_from(a[0])
+                    + u32::cast_from(a[1])
+                    + u32::cast_from(b[0])
+                    + u32::cast_from(b[1]);
+                *dst = T::cast_from((sum + 2) >> 2);
+            }
+        }
+
+        new
+    }
+
     /// Returns a plane downscaled from the source plane by a factor of `scale` (not padded)
     pub fn downscale<const SCALE: usize>(&self) -> Plane<T> {
         let mut new_plane = Plane::new(self.cfg.width / SCALE, self.cfg.height / SCALE, 0, 0, 0, 0);
@@ -557,7 +1103,7 @@ impl<T: Pixel> Plane<T> {
                             }
 
                             // Box average
-                            let avg = sum as usize / box_pixels;
+                            let avg = (sum / box_pixels as $x) as usize;
                             *dst = T::cast_from(avg);
                         };
                     }
@@ -569,8 +1115,13 @@ impl<T: Pixel> Plane<T> {
                             <= u16::MAX as u128
                     {
                         generate_inner_loop!(u16);
-                    } else {
+                    } else if SCALE as u128 * SCALE as u128 * (u16::MAX as u128)
+                        + half_box_pixels as u128
+                        <= u32::MAX as u128
+                    {
                         generate_inner_loop!(u32);
+                    } else {
+                        generate_inner_loop!(u64);
                     }
                 }
             }
@@ -584,18 +1135,43 @@ impl<T: Pixel> Plane<T> {
 
     /// Iterates over the lines of the plane
     pub fn rows_iter(&self) -> RowsIter<'_, T> {
+        self.rows_range(0, self.cfg.height)
+    }
+
+    pub fn rows_iter_mut(&mut self) -> RowsIterMut<'_, T> {
+        let height = self.cfg.height;
+        self.rows_range_mut(0, height)
+    }
+
+    /// Iterates over the lines of the plane in `start..end`.
+    ///
+    /// `end` is clamped to the height of the plane, so the iterator is empty
+    /// when `start` is past the last line.
+    pub fn rows_range(&self, start: usize, end: usize) -> RowsIter<'_, T> {
+        let start = start.min(self.cfg.height);
+        let end = end.clamp(start, self.cfg.height);
+
         RowsIter {
             plane: self,
             x: 0,
-            y: 0,
+            y: start as isize,
+            end: end as isize,
         }
     }
 
-    pub fn rows_iter_mut(&mut self) -> RowsIterMut<'_, T> {
+    /// Iterates mutably over the lines of the plane in `start..end`.
+    ///
+    /// `end` is clamped to the height of the plane, so the iterator is empty
+    /// when `start` is past the last line.
+    pub fn rows_range_mut(&mut self, start: usize, end: usize) -> RowsIterMut<'_, T> {
+        let start = start.min(self.cfg.height);
+        let end = end.clamp(start, self.cfg.height);
+
         RowsIterMut {
             plane: self as *mut Plane<T>,
             x: 0,
-            y: 0,
+            y: start as isize,
+            end: end as isize,
             phantom: PhantomData,
         }
     }
@@ -606,638 +1182,3515 @@ impl<T: Pixel> Plane<T> {
 
         &self.data[range]
     }
-}
 
-/// Iterator over plane pixels, skipping padding.
-#[derive(Debug)]
-pub struct PlaneIter<'a, T: Pixel> {
-    plane: &'a Plane<T>,
-    y: usize,
-    x: usize,
-}
+    /// Checks that `other` has the same visible dimensions as this plane.
+    pub(crate) fn check_dimensions(&self, other: &Plane<T>) -> Result<(), Error> {
+        if self.cfg.width != other.cfg.width || self.cfg.height != other.cfg.height {
+            return Err(Error::DimensionMismatch {
+                expected: (self.cfg.width, self.cfg.height),
+                found: (other.cfg.width, other.cfg.height),
+            });
+        }
 
-impl<'a, T: Pixel> PlaneIter<'a, T> {
-    /// Creates a new iterator.
-    pub fn new(plane: &'a Plane<T>) -> Self {
-        Self { plane, y: 0, x: 0 }
+        Ok(())
     }
 
-    fn width(&self) -> usize {
-        self.plane.cfg.width
+    /// Returns the sum of squared errors between the visible pixels of two planes.
+    ///
+    /// # Errors
+    ///
+    /// - If the planes do not have the same width and height
+    pub fn sse(&self, other: &Plane<T>) -> Result<u64, Error> {
+        self.check_dimensions(other)?;
+
+        Ok(self
+            .rows_iter()
+            .zip(other.rows_iter())
+            .map(|(a, b)| {
+                a.iter()
+                    .zip(b)
+                    .map(|(&a, &b)| {
+                        let diff = u64::from(u32::cast_from(a).abs_diff(u32::cast_from(b)));
+                        diff * diff
+                    })
+                    .sum::<u64>()
+            })
+            .sum())
     }
 
-    fn height(&self) -> usize {
-        self.plane.cfg.height
+    /// Returns the sum of absolute differences between the visible pixels of
+    /// two planes, as used for block matching in motion estimation.
+    ///
+    /// # Errors
+    ///
+    /// - If the planes do not have the same width and height
+    pub fn sad(&self, other: &Plane<T>) -> Result<u64, Error> {
+        self.check_dimensions(other)?;
+
+        Ok(self
+            .rows_iter()
+            .zip(other.rows_iter())
+            .map(|(a, b)| {
+                a.iter()
+                    .zip(b)
+                    .map(|(&a, &b)| u64::from(u32::cast_from(a).abs_diff(u32::cast_from(b))))
+                    .sum::<u64>()
+            })
+            .sum())
     }
-}
 
-impl<'a, T: Pixel> Iterator for PlaneIter<'a, T> {
-    type Item = T;
+    /// Returns a new plane with the pixels of this plane rearranged so that
+    /// the pixel at `(x, y)` in the new plane is read from `src_pos(x, y)`.
+    ///
+    /// The new plane has its width and height swapped and is not padded.
+    fn remapped_transposed(&self, src_pos: impl Fn(usize, usize) -> (usize, usize)) -> Plane<T> {
+        let mut new = Plane::new(
+            self.cfg.height,
+            self.cfg.width,
+            self.cfg.ydec,
+            self.cfg.xdec,
+            0,
+            0,
+        );
 
-    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
-        if self.y == self.height() {
-            return None;
-        }
-        let pixel = self.plane.p(self.x, self.y);
-        if self.x == self.width() - 1 {
-            self.x = 0;
-            self.y += 1;
-        } else {
-            self.x += 1;
+        for (y, dst_row) in new.rows_iter_mut().enumerate() {
+            for (x, dst) in dst_row.iter_mut().enumerate() {
+                let (src_x, src_y) = src_pos(x, y);
+                *dst = self.p(src_x, src_y);
+            }
         }
-        Some(pixel)
+
+        new
     }
-}
 
-impl<T: Pixel> FusedIterator for PlaneIter<'_, T> {}
+    /// Returns the transpose of the plane (not padded).
+    pub fn transpose(&self) -> Plane<T> {
+        self.remapped_transposed(|x, y| (y, x))
+    }
 
-// A Plane, PlaneSlice, or PlaneRegion is assumed to include or be able to include
-// padding on the edge of the frame
-#[derive(Clone, Copy, Debug)]
-pub struct PlaneSlice<'a, T: Pixel> {
-    pub plane: &'a Plane<T>,
-    pub x: isize,
-    pub y: isize,
-}
+    /// Returns the plane rotated by 90 degrees clockwise (not padded).
+    pub fn rotate_90_cw(&self) -> Plane<T> {
+        let height = self.cfg.height;
+        self.remapped_transposed(|x, y| (y, height - 1 - x))
+    }
 
-// A RowsIter or RowsIterMut is assumed to crop the padding from the frame edges
-pub struct RowsIter<'a, T: Pixel> {
-    plane: &'a Plane<T>,
-    x: isize,
-    y: isize,
-}
+    /// Returns the plane rotated by 90 degrees counter-clockwise (not padded).
+    pub fn rotate_90_ccw(&self) -> Plane<T> {
+        let width = self.cfg.width;
+        self.remapped_transposed(|x, y| (width - 1 - y, x))
+    }
 
-impl<'a, T: Pixel> Iterator for RowsIter<'a, T> {
-    type Item = &'a [T];
+    /// Mirrors the visible pixels of the plane horizontally, in place.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.rows_iter_mut() {
+            row.reverse();
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.plane.cfg.height as isize > self.y {
-            // cannot directly return self.ps.row(row) due to lifetime issue
-            let range = self.plane.row_range_cropped(self.x, self.y);
-            self.y += 1;
-            Some(&self.plane.data[range])
-        } else {
-            None
+    /// Mirrors the visible pixels of the plane vertically, in place.
+    pub fn flip_vertical(&mut self) {
+        let stride = self.cfg.stride;
+        let width = self.cfg.width;
+        let height = self.cfg.height;
+        let data = self.data_origin_mut();
+
+        for y in 0..height / 2 {
+            let (top, bottom) = data.split_at_mut((height - 1 - y) * stride);
+            top[y * stride..][..width].swap_with_slice(&mut bottom[..width]);
         }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.plane.cfg.height as isize - self.y;
-        debug_assert!(remaining >= 0);
-        let remaining = remaining as usize;
+    /// Returns the sum of the visible pixels.
+    pub fn sum(&self) -> u64 {
+        self.rows_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&p| u64::from(u32::cast_from(p)))
+                    .sum::<u64>()
+            })
+            .sum()
+    }
 
-        (remaining, Some(remaining))
+    /// Returns the mean of the visible pixels, or `NaN` for an empty plane.
+    pub fn mean(&self) -> f64 {
+        self.sum() as f64 / (self.cfg.width * self.cfg.height) as f64
     }
-}
 
-impl<'a, T: Pixel> ExactSizeIterator for RowsIter<'a, T> {}
-impl<'a, T: Pixel> FusedIterator for RowsIter<'a, T> {}
+    /// Returns the smallest visible pixel, or `None` for an empty plane.
+    pub fn min(&self) -> Option<T> {
+        self.rows_iter()
+            .filter_map(|row| row.iter().copied().min())
+            .min()
+    }
 
-impl<'a, T: Pixel> PlaneSlice<'a, T> {
-    #[allow(unused)]
-    pub fn as_ptr(&self) -> *const T {
-        self[0].as_ptr()
+    /// Returns the largest visible pixel, or `None` for an empty plane.
+    pub fn max(&self) -> Option<T> {
+        self.rows_iter()
+            .filter_map(|row| row.iter().copied().max())
+            .max()
     }
 
-    pub fn rows_iter(&self) -> RowsIter<'_, T> {
-        RowsIter {
-            plane: self.plane,
-            x: self.x,
-            y: self.y,
+    /// Returns the number of visible pixels for each value representable
+    /// with `bit_depth` bits.
+    ///
+    /// # Errors
+    ///
+    /// - If `bit_depth` is zero or does not fit in `T`
+    /// - If a visible pixel does not fit in `bit_depth` bits
+    pub fn histogram(&self, bit_depth: usize) -> Result<Vec<u32>, Error> {
+        if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+            return Err(Error::UnsupportedBitDepth { bit_depth });
         }
-    }
 
-    pub fn clamp(&self) -> PlaneSlice<'a, T> {
-        PlaneSlice {
-            plane: self.plane,
-            x: self.x.clamp(
-                -(self.plane.cfg.xorigin as isize),
-                self.plane.cfg.width as isize,
-            ),
-            y: self.y.clamp(
-                -(self.plane.cfg.yorigin as isize),
-                self.plane.cfg.height as isize,
-            ),
+        let mut histogram = vec![0; 1 << bit_depth];
+        for row in self.rows_iter() {
+            for &p in row {
+                *histogram
+                    .get_mut(u32::cast_from(p) as usize)
+                    .ok_or(Error::UnsupportedBitDepth { bit_depth })? += 1;
+            }
         }
+
+        Ok(histogram)
     }
 
-    pub fn subslice(&self, xo: usize, yo: usize) -> PlaneSlice<'a, T> {
-        PlaneSlice {
-            plane: self.plane,
-            x: self.x + xo as isize,
-            y: self.y + yo as isize,
+    /// Clamps the visible pixels to the range representable with
+    /// `bit_depth` bits.
+    pub fn clamp_to_depth(&mut self, bit_depth: usize) {
+        for row in self.rows_iter_mut() {
+            for p in row {
+                *p = p.clamp_to_depth(bit_depth);
+            }
         }
     }
 
-    pub fn reslice(&self, xo: isize, yo: isize) -> PlaneSlice<'a, T> {
-        PlaneSlice {
-            plane: self.plane,
-            x: self.x + xo,
-            y: self.y + yo,
+    /// Sets all the visible pixels of the plane to `value`.
+    pub fn fill(&mut self, value: T) {
+        if self.cfg.stride == self.cfg.width {
+            // The visible pixels are contiguous, so fill them all at once
+            let len = self.cfg.width * self.cfg.height;
+            self.data_origin_mut()[..len].fill(value);
+            return;
         }
-    }
 
-    /// A slice starting i pixels above the current one.
-    pub fn go_up(&self, i: usize) -> PlaneSlice<'a, T> {
-        PlaneSlice {
-            plane: self.plane,
-            x: self.x,
-            y: self.y - i as isize,
+        for row in self.rows_iter_mut() {
+            row.fill(value);
         }
     }
 
-    /// A slice starting i pixels to the left of the current one.
-    pub fn go_left(&self, i: usize) -> PlaneSlice<'a, T> {
-        PlaneSlice {
-            plane: self.plane,
-            x: self.x - i as isize,
-            y: self.y,
+    /// Adds the visible pixels of `other` to this plane, saturating at the
+    /// largest value representable with `bit_depth` bits, capped to the
+    /// largest value of the type.
+    ///
+    /// # Errors
+    ///
+    /// - If the planes do not have the same width and height
+    pub fn saturating_add_plane(
+        &mut self,
+        other: &Plane<T>,
+        bit_depth: usize,
+    ) -> Result<(), Error> {
+        self.check_dimensions(other)?;
+
+        let max = u32::cast_from(T::max_for_depth(bit_depth));
+        for (dst_row, src_row) in self.rows_iter_mut().zip(other.rows_iter()) {
+            for (dst, &src) in dst_row.iter_mut().zip(src_row) {
+                *dst = T::cast_from((u32::cast_from(*dst) + u32::cast_from(src)).min(max));
+            }
         }
-    }
 
-    pub fn p(&self, add_x: usize, add_y: usize) -> T {
-        let new_y = (self.y + add_y as isize + self.plane.cfg.yorigin as isize) as usize;
-        let new_x = (self.x + add_x as isize + self.plane.cfg.xorigin as isize) as usize;
-        self.plane.data[new_y * self.plane.cfg.stride + new_x]
+        Ok(())
     }
 
-    /// Checks if `add_y` and `add_x` lies in the allocated bounds of the
-    /// underlying plane.
-    pub fn accessible(&self, add_x: usize, add_y: usize) -> bool {
-        let y = (self.y + add_y as isize + self.plane.cfg.yorigin as isize) as usize;
-        let x = (self.x + add_x as isize + self.plane.cfg.xorigin as isize) as usize;
-        y < self.plane.cfg.alloc_height && x < self.plane.cfg.stride
-    }
+    /// Subtracts the visible pixels of `other` from this plane, saturating
+    /// at zero.
+    ///
+    /// # Errors
+    ///
+    /// - If the planes do not have the same width and height
+    pub fn saturating_sub_plane(&mut self, other: &Plane<T>) -> Result<(), Error> {
+        self.check_dimensions(other)?;
 
-    /// Checks if -`sub_x` and -`sub_y` lies in the allocated bounds of the
-    /// underlying plane.
-    pub fn accessible_neg(&self, sub_x: usize, sub_y: usize) -> bool {
-        let y = self.y - sub_y as isize + self.plane.cfg.yorigin as isize;
-        let x = self.x - sub_x as isize + self.plane.cfg.xorigin as isize;
-        y >= 0 && x >= 0
-    }
+        for (dst_row, src_row) in self.rows_iter_mut().zip(other.rows_iter()) {
+            for (dst, &src) in dst_row.iter_mut().zip(src_row) {
+                *dst = dst.saturating_sub(src);
+            }
+        }
 
-    /// This version of the function crops off the padding on the right side of the image
-    pub fn row_cropped(&self, y: usize) -> &[T] {
-        let y = (self.y + y as isize + self.plane.cfg.yorigin as isize) as usize;
-        let x = (self.x + self.plane.cfg.xorigin as isize) as usize;
-        let start = y * self.plane.cfg.stride + x;
-        let width = (self.plane.cfg.width as isize - self.x) as usize;
-        &self.plane.data[start..start + width]
+        Ok(())
     }
 
-    /// This version of the function includes the padding on the right side of the image
-    pub fn row(&self, y: usize) -> &[T] {
-        let y = (self.y + y as isize + self.plane.cfg.yorigin as isize) as usize;
-        let x = (self.x + self.plane.cfg.xorigin as isize) as usize;
-        let start = y * self.plane.cfg.stride + x;
-        let width = self.plane.cfg.stride - x;
-        &self.plane.data[start..start + width]
+    /// Blends the visible pixels of `other` into this plane, computing
+    /// `self * (1 - alpha) + other * alpha` rounded to the nearest integer.
+    ///
+    /// `alpha` is expected to be within `0.0..=1.0`. Values outside of it
+    /// extrapolate, with the result clamped to the range of the pixel type.
+    ///
+    /// # Errors
+    ///
+    /// - If the planes do not have the same width and height
+    #[cfg(feature = "std")]
+    pub fn blend(&mut self, other: &Plane<T>, alpha: f32) -> Result<(), Error> {
+        self.check_dimensions(other)?;
+
+        let max = u32::cast_from(T::max_value()) as f32;
+        for (dst_row, src_row) in self.rows_iter_mut().zip(other.rows_iter()) {
+            for (dst, &src) in dst_row.iter_mut().zip(src_row) {
+                let a = u32::cast_from(*dst) as f32;
+                let b = u32::cast_from(src) as f32;
+                *dst = T::cast_from((b - a).mul_add(alpha, a).round().clamp(0.0, max) as u32);
+            }
+        }
+
+        Ok(())
     }
-}
 
-impl<'a, T: Pixel> Index<usize> for PlaneSlice<'a, T> {
-    type Output = [T];
-    fn index(&self, index: usize) -> &Self::Output {
-        let range = self.plane.row_range(self.x, self.y + index as isize);
-        &self.plane.data[range]
+    /// Returns the largest absolute difference between the visible pixels of
+    /// two planes, or `None` if their dimensions differ.
+    pub fn max_abs_diff(&self, other: &Plane<T>) -> Option<u16> {
+        self.check_dimensions(other).ok()?;
+
+        Some(
+            self.rows_iter()
+                .zip(other.rows_iter())
+                .map(|(a, b)| {
+                    a.iter()
+                        .zip(b)
+                        .map(|(&a, &b)| {
+                            u16::cast_from(u32::cast_from(a).abs_diff(u32::cast_from(b)))
+                        })
+                        .max()
+                        .unwrap_or(0)
+                })
+                .max()
+                .unwrap_or(0),
+        )
     }
-}
 
-pub struct PlaneMutSlice<'a, T: Pixel> {
-    pub plane: &'a mut Plane<T>,
-    pub x: isize,
-    pub y: isize,
-}
+    /// Returns the plane resized to `width` and `height` using the given
+    /// interpolation filter (not padded).
+    ///
+    /// Results are rounded to the nearest integer and clamped to the range
+    /// representable with `bit_depth` bits, capped to the range of the type.
+    ///
+    /// # Panics
+    ///
+    /// - If the plane is empty and the requested dimensions are not
+    #[cfg(feature = "std")]
+    pub fn resize(
+        &self,
+        width: usize,
+        height: usize,
+        filter: ResizeFilter,
+        bit_depth: usize,
+    ) -> Plane<T> {
+        let mut new = Plane::new(width, height, self.cfg.xdec, self.cfg.ydec, 0, 0);
+        if width == 0 || height == 0 {
+            return new;
+        }
+        assert!(self.cfg.width != 0 && self.cfg.height != 0);
+
+        let max = u32::cast_from(T::max_for_depth(bit_depth)) as f32;
+        let horizontal = filter.contributions(self.cfg.width, width);
+        let vertical = filter.contributions(self.cfg.height, height);
+
+        // Horizontal pass, into an intermediate buffer of `width` columns
+        let mut tmp = Vec::with_capacity(width * self.cfg.height);
+        for row in self.rows_iter() {
+            for (start, weights) in &horizontal {
+                let sum = row[*start..]
+                    .iter()
+                    .zip(weights)
+                    .fold(0.0f32, |acc, (&p, &w)| {
+                        w.mul_add(u32::cast_from(p) as f32, acc)
+                    });
+                tmp.push(sum);
+            }
+        }
 
-pub struct RowsIterMut<'a, T: Pixel> {
-    plane: *mut Plane<T>,
-    x: isize,
-    y: isize,
-    phantom: PhantomData<&'a mut Plane<T>>,
-}
+        // Vertical pass
+        for (dst_row, (start, weights)) in new.rows_iter_mut().zip(&vertical) {
+            for (x, dst) in dst_row.iter_mut().enumerate() {
+                let sum = tmp[start * width + x..]
+                    .iter()
+                    .step_by(width)
+                    .zip(weights)
+                    .fold(0.0f32, |acc, (&v, &w)| w.mul_add(v, acc));
+                *dst = T::cast_from(sum.round().clamp(0.0, max) as u32);
+            }
+        }
 
-impl<'a, T: Pixel> Iterator for RowsIterMut<'a, T> {
-    type Item = &'a mut [T];
+        new
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // SAFETY: there could not be a concurrent call using a mutable reference to the plane
-        let plane = unsafe { &mut *self.plane };
-        if plane.cfg.height as isize > self.y {
-            // cannot directly return self.ps.row(row) due to lifetime issue
-            let range = plane.row_range_cropped(self.x, self.y);
-            self.y += 1;
-            Some(&mut plane.data[range])
-        } else {
-            None
+    /// Sets each visible pixel of the plane to the value returned by `f`
+    /// for its coordinates, in row-major order.
+    pub fn fill_with(&mut self, mut f: impl FnMut(usize, usize) -> T) {
+        for (y, row) in self.rows_iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = f(x, y);
+            }
         }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        // SAFETY: there could not be a concurrent call using a mutable reference to the plane
-        let plane = unsafe { &mut *self.plane };
-        let remaining = plane.cfg.height as isize - self.y;
-        debug_assert!(remaining >= 0);
-        let remaining = remaining as usize;
-
-        (remaining, Some(remaining))
+    /// Exchanges the contents of two planes, without copying any pixel.
+    pub fn swap_with(&mut self, other: &mut Plane<T>) {
+        core::mem::swap(self, other);
     }
-}
-
-impl<'a, T: Pixel> ExactSizeIterator for RowsIterMut<'a, T> {}
-impl<'a, T: Pixel> FusedIterator for RowsIterMut<'a, T> {}
 
-impl<'a, T: Pixel> PlaneMutSlice<'a, T> {
-    #[allow(unused)]
-    pub fn rows_iter(&self) -> RowsIter<'_, T> {
-        RowsIter {
-            plane: self.plane,
-            x: self.x,
-            y: self.y,
+    /// Replaces each visible pixel of the plane with the value returned by
+    /// `f` for it. The padding is left untouched.
+    pub fn map_in_place(&mut self, mut f: impl FnMut(T) -> T) {
+        for row in self.rows_iter_mut() {
+            for pixel in row {
+                *pixel = f(*pixel);
+            }
         }
     }
 
-    pub fn rows_iter_mut(&mut self) -> RowsIterMut<'_, T> {
-        RowsIterMut {
-            plane: self.plane as *mut Plane<T>,
-            x: self.x,
-            y: self.y,
-            phantom: PhantomData,
+    /// Replaces each visible pixel of the plane with the value returned by
+    /// `f` for its coordinates and value. The padding is left untouched.
+    pub fn map_indexed_in_place(&mut self, mut f: impl FnMut(usize, usize, T) -> T) {
+        for (y, row) in self.rows_iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = f(x, y, *pixel);
+            }
         }
     }
 
-    #[allow(unused)]
-    pub fn subslice(&mut self, xo: usize, yo: usize) -> PlaneMutSlice<'_, T> {
-        PlaneMutSlice {
-            plane: self.plane,
-            x: self.x + xo as isize,
-            y: self.y + yo as isize,
-        }
+    /// Iterates over the visible pixels of the plane in row-major order,
+    /// along with their `(x, y)` coordinates.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        self.rows_iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &pixel)| (x, y, pixel)))
     }
-}
 
-impl<'a, T: Pixel> Index<usize> for PlaneMutSlice<'a, T> {
-    type Output = [T];
-    fn index(&self, index: usize) -> &Self::Output {
-        let range = self.plane.row_range(self.x, self.y + index as isize);
-        &self.plane.data[range]
+    /// Iterates mutably over the visible pixels of the plane in row-major
+    /// order, along with their `(x, y)` coordinates.
+    pub fn enumerate_pixels_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut T)> + '_ {
+        self.rows_iter_mut().enumerate().flat_map(|(y, row)| {
+            row.iter_mut()
+                .enumerate()
+                .map(move |(x, pixel)| (x, y, pixel))
+        })
     }
-}
 
-impl<'a, T: Pixel> IndexMut<usize> for PlaneMutSlice<'a, T> {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        let range = self.plane.row_range(self.x, self.y + index as isize);
-        &mut self.plane.data[range]
-    }
-}
+    /// Returns the visible pixels of row `y` as little-endian bytes, or `None`
+    /// if `y` is not a visible row.
+    pub fn row_bytes(&self, y: usize) -> Option<Vec<u8>> {
+        if y >= self.cfg.height {
+            return None;
+        }
 
-#[cfg(test)]
-pub mod test {
-    use super::*;
+        let mut buf = Vec::with_capacity(self.cfg.width * size_of::<T>());
+        self.row_bytes_into(y, &mut buf);
+        Some(buf)
+    }
 
-    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
-    use wasm_bindgen_test::*;
+    /// Replaces the contents of `buf` with the visible pixels of row `y` as
+    /// little-endian bytes.
+    ///
+    /// Reusing `buf` across rows avoids allocating for each of them.
+    ///
+    /// # Panics
+    ///
+    /// - If `y` is not a visible row
+    pub fn row_bytes_into(&self, y: usize, buf: &mut Vec<u8>) {
+        let bytewidth = size_of::<T>();
 
-    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
-    wasm_bindgen_test_configure!(run_in_browser);
+        buf.clear();
+        for &pixel in &self[y] {
+            buf.extend_from_slice(&u16::cast_from(pixel).to_le_bytes()[..bytewidth]);
+        }
+    }
 
-    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
-    #[test]
-    fn copy_from_raw_u8() {
-        #[rustfmt::skip]
-        let mut plane = Plane::from_slice(&[
-            0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 1, 2, 3, 4, 0, 0,
-            0, 0, 8, 7, 6, 5, 0, 0,
-            0, 0, 9, 8, 7, 6, 0, 0,
-            0, 0, 2, 3, 4, 5, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0,
-        ], 8);
+    /// Returns the size in bytes of the visible pixels of the plane.
+    pub fn visible_byte_len(&self) -> usize {
+        self.cfg.width * self.cfg.height * size_of::<T>()
+    }
 
-        let input = vec![42u8; 64];
+    /// Returns the size in bytes of the buffer of the plane, including the
+    /// padding.
+    pub fn allocated_byte_len(&self) -> usize {
+        self.data.len() * size_of::<T>()
+    }
 
-        plane.copy_from_raw_u8(&input, 8, 1);
+    /// Splits an interlaced plane into its top and bottom fields (not padded),
+    /// made of its even and odd rows respectively.
+    ///
+    /// # Panics
+    ///
+    /// - If the height of the plane is odd
+    pub fn separate_fields(&self) -> (Plane<T>, Plane<T>) {
+        assert!(
+            self.cfg.height % 2 == 0,
+            "plane height ({}) must be even",
+            self.cfg.height
+        );
 
-        println!("{:?}", &plane.data[..10]);
+        let field = |parity: usize| {
+            let mut new = Plane::new(
+                self.cfg.width,
+                self.cfg.height / 2,
+                self.cfg.xdec,
+                self.cfg.ydec,
+                0,
+                0,
+            );
+            for (dst, src) in new
+                .rows_iter_mut()
+                .zip(self.rows_iter().skip(parity).step_by(2))
+            {
+                dst.copy_from_slice(src);
+            }
+            new
+        };
 
-        assert_eq!(&input[..64], &plane.data[..64]);
+        (field(0), field(1))
     }
 
-    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
-    #[test]
-    fn copy_to_raw_u8() {
+    /// Interleaves the rows of a top and a bottom field into a plane (not
+    /// padded), undoing [`Plane::separate_fields`].
+    ///
+    /// # Errors
+    ///
+    /// - If the fields differ in dimensions
+    pub fn weave_fields(top: &Plane<T>, bottom: &Plane<T>) -> Result<Plane<T>, Error> {
+        top.check_dimensions(bottom)?;
+
+        let mut new = Plane::new(
+            top.cfg.width,
+            top.cfg.height * 2,
+            top.cfg.xdec,
+            top.cfg.ydec,
+            0,
+            0,
+        );
+        for (y, dst) in new.rows_iter_mut().enumerate() {
+            let field = if y % 2 == 0 { top } else { bottom };
+            dst.copy_from_slice(&field[y / 2]);
+        }
+
+        Ok(new)
+    }
+
+    /// Copies the visible pixels of `src` into the visible area of the plane,
+    /// row by row. The padding is left untouched.
+    ///
+    /// Unlike cloning, this works between planes with different strides or
+    /// padding, such as from a padded working buffer into a packed one.
+    ///
+    /// # Errors
+    ///
+    /// - If `src` differs in dimensions from this plane
+    pub fn copy_from(&mut self, src: &Plane<T>) -> Result<(), Error> {
+        self.check_dimensions(src)?;
+
+        for (dst, src) in self.rows_iter_mut().zip(src.rows_iter()) {
+            dst.copy_from_slice(src);
+        }
+
+        Ok(())
+    }
+
+    /// Copies the whole buffer of this plane, including the padding, into
+    /// `dst` without reallocating it.
+    ///
+    /// Unlike [`Plane::copy_from`], the layout of both planes must match, so
+    /// that `dst` becomes identical to a clone of this plane. This allows
+    /// reusing the buffers of a pool of planes instead of cloning.
+    ///
+    /// # Errors
+    ///
+    /// - If `dst` differs in dimensions from this plane
+    /// - If `dst` differs in stride, padding or decimation from this plane
+    pub fn clone_into(&self, dst: &mut Plane<T>) -> Result<(), Error> {
+        self.check_dimensions(dst)?;
+        if self.cfg != dst.cfg {
+            return Err(Error::InvalidPlaneConfig);
+        }
+
+        dst.data.copy_from_slice(&self.data);
+
+        Ok(())
+    }
+
+    /// Returns the visible pixels of the plane as a single slice, without
+    /// copying, if they are contiguous in memory.
+    ///
+    /// This is the case when the stride equals the width, i.e. the plane has
+    /// no horizontal padding and no alignment slack. Planes with horizontal
+    /// padding return `None`, and the caller should fall back to
+    /// [`Plane::rows_iter`].
+    pub fn as_contiguous(&self) -> Option<&[T]> {
+        if self.cfg.stride != self.cfg.width {
+            return None;
+        }
+
+        Some(&self.data_origin()[..self.cfg.width * self.cfg.height])
+    }
+
+    /// Writes the absolute difference between the visible pixels of this
+    /// plane and `other` into the visible pixels of `dst`.
+    ///
+    /// # Errors
+    ///
+    /// - If `other` or `dst` differs in dimensions from this plane
+    pub fn abs_diff_into(&self, other: &Plane<T>, dst: &mut Plane<T>) -> Result<(), Error> {
+        self.scaled_abs_diff_into(other, dst, 1, 8 * size_of::<T>())
+    }
+
+    /// Writes the absolute difference between the visible pixels of this
+    /// plane and `other`, multiplied by `gain` and clamped to the range of
+    /// `bit_depth`, into the visible pixels of `dst`.
+    ///
+    /// Amplifying the difference makes small errors visible when inspecting
+    /// the result.
+    ///
+    /// # Errors
+    ///
+    /// - If `other` or `dst` differs in dimensions from this plane
+    pub fn scaled_abs_diff_into(
+        &self,
+        other: &Plane<T>,
+        dst: &mut Plane<T>,
+        gain: u32,
+        bit_depth: usize,
+    ) -> Result<(), Error> {
+        self.check_dimensions(other)?;
+        self.check_dimensions(dst)?;
+
+        let max = u32::cast_from(T::max_for_depth(bit_depth));
+        for ((a, b), dst) in self
+            .rows_iter()
+            .zip(other.rows_iter())
+            .zip(dst.rows_iter_mut())
+        {
+            for ((&a, &b), dst) in a.iter().zip(b).zip(dst) {
+                let diff = u32::cast_from(a).abs_diff(u32::cast_from(b));
+                *dst = T::cast_from(diff.saturating_mul(gain).min(max));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies one channel of tightly packed interleaved data, such as packed
+    /// RGB or YUYV, into the visible pixels of the plane.
+    ///
+    /// `src` holds `num_channels` samples of `bytes_per_sample` bytes for each
+    /// pixel, in row-major order without any padding, and sample `channel` of
+    /// each pixel is copied. Samples wider than a byte are little-endian.
+    ///
+    /// # Errors
+    ///
+    /// - If `channel` is not below `num_channels`
+    /// - If the length of `src` does not match the dimensions of the plane
+    ///
+    /// # Panics
+    ///
+    /// - If `bytes_per_sample` is not 1 or 2, or is 2 for a `Plane<u8>`
+    pub fn copy_from_interleaved(
+        &mut self,
+        src: &[u8],
+        num_channels: usize,
+        channel: usize,
+        bytes_per_sample: usize,
+    ) -> Result<(), Error> {
+        assert!(
+            bytes_per_sample == 1 || (bytes_per_sample == 2 && size_of::<T>() == 2),
+            "bytes per sample ({}) cannot fit in the plane",
+            bytes_per_sample
+        );
+        if channel >= num_channels {
+            return Err(Error::InvalidChannel {
+                channel,
+                num_channels,
+            });
+        }
+
+        let pixel_size = num_channels * bytes_per_sample;
+        let expected = self.cfg.width * self.cfg.height * pixel_size;
+        if src.len() != expected {
+            return Err(Error::DataLength {
+                expected,
+                found: src.len(),
+            });
+        }
+
+        let width = self.cfg.width;
+        for (dst, src) in self
+            .rows_iter_mut()
+            .zip(src.chunks_exact((width * pixel_size).max(1)))
+        {
+            for (dst, pixel) in dst.iter_mut().zip(src.chunks_exact(pixel_size)) {
+                let sample = &pixel[channel * bytes_per_sample..][..bytes_per_sample];
+                *dst = match *sample {
+                    [low] => T::cast_from(low),
+                    [low, high] => T::cast_from(u16::from_le_bytes([low, high])),
+                    _ => unreachable!(),
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rescales the visible pixels from `from_bit_depth` to `to_bit_depth`.
+    ///
+    /// Increasing the bit depth shifts the values left, while decreasing it
+    /// shifts them right with rounding. The results are clamped to the range
+    /// of `to_bit_depth`.
+    ///
+    /// # Errors
+    ///
+    /// - If either bit depth is zero or does not fit in `T`, in which case the
+    ///   plane is left unmodified
+    pub fn rescale_bit_depth(
+        &mut self,
+        from_bit_depth: usize,
+        to_bit_depth: usize,
+    ) -> Result<(), Error> {
+        for bit_depth in [from_bit_depth, to_bit_depth] {
+            if bit_depth == 0 || bit_depth > 8 * size_of::<T>() {
+                return Err(Error::UnsupportedBitDepth { bit_depth });
+            }
+        }
+
+        let max = u32::cast_from(T::max_for_depth(to_bit_depth));
+
+        self.map_in_place(|p| {
+            let p = u32::cast_from(p);
+            let rescaled = if to_bit_depth >= from_bit_depth {
+                p << (to_bit_depth - from_bit_depth)
+            } else {
+                let shift = from_bit_depth - to_bit_depth;
+                (p + (1 << (shift - 1))) >> shift
+            };
+            T::cast_from(rescaled.min(max))
+        });
+
+        Ok(())
+    }
+
+    /// Returns the whole buffer of the plane as a slice, without copying, if
+    /// it consists of the visible pixels only.
+    ///
+    /// Unlike [`Plane::as_contiguous`], this also requires the plane to have
+    /// no vertical padding, so the slice covers the entire allocation.
+    ///
+    /// # Errors
+    ///
+    /// - If the buffer contains padding or alignment slack
+    pub fn try_as_slice(&self) -> Result<&[T], Error> {
+        if self.cfg.stride != self.cfg.width || self.data.len() != self.cfg.width * self.cfg.height
+        {
+            return Err(Error::Padded);
+        }
+
+        Ok(&self.data)
+    }
+
+    /// Returns the visible pixels of the plane tightly packed with `bit_depth`
+    /// bits each, most significant bit first, in row-major order.
+    ///
+    /// The last byte is padded with zero bits. Bits of the pixels above
+    /// `bit_depth` are discarded.
+    ///
+    /// # Panics
+    ///
+    /// - If `bit_depth` is not between 1 and 16
+    pub fn packed_bits(&self, bit_depth: usize) -> Vec<u8> {
+        assert!((1..=16).contains(&bit_depth));
+
+        let mask = (1u32 << bit_depth) - 1;
+        let mut out = Vec::with_capacity(Self::packed_len(&self.cfg, bit_depth));
+        let mut acc = 0u32;
+        let mut bits = 0;
+
+        for &pixel in self.rows_iter().flatten() {
+            acc = (acc << bit_depth) | (u32::cast_from(pixel) & mask);
+            bits += bit_depth;
+            while bits >= 8 {
+                bits -= 8;
+                out.push((acc >> bits) as u8);
+            }
+            acc &= (1 << bits) - 1;
+        }
+        if bits > 0 {
+            out.push((acc << (8 - bits)) as u8);
+        }
+
+        out
+    }
+
+    /// Copies tightly packed pixels of `bit_depth` bits each, as produced by
+    /// [`Plane::packed_bits`], into the visible pixels of the plane.
+    ///
+    /// # Errors
+    ///
+    /// - If the length of `src` does not match the dimensions of the plane
+    ///
+    /// # Panics
+    ///
+    /// - If `bit_depth` is not between 1 and 16
+    pub fn copy_from_packed_bits(&mut self, src: &[u8], bit_depth: usize) -> Result<(), Error> {
+        assert!((1..=16).contains(&bit_depth));
+
+        let expected = Self::packed_len(&self.cfg, bit_depth);
+        if src.len() != expected {
+            return Err(Error::DataLength {
+                expected,
+                found: src.len(),
+            });
+        }
+
+        let mask = (1u32 << bit_depth) - 1;
+        let mut bytes = src.iter();
+        let mut acc = 0u32;
+        let mut bits = 0;
+
+        for pixel in self.rows_iter_mut().flatten() {
+            while bits < bit_depth {
+                // The length check guarantees enough bytes for all pixels
+                acc = (acc << 8) | u32::from(*bytes.next().unwrap());
+                bits += 8;
+            }
+            bits -= bit_depth;
+            *pixel = T::cast_from((acc >> bits) & mask);
+            acc &= (1 << bits) - 1;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of bytes taken by the visible pixels of a plane
+    /// packed with `bit_depth` bits each.
+    const fn packed_len(cfg: &PlaneConfig, bit_depth: usize) -> usize {
+        (cfg.width * cfg.height * bit_depth + 7) / 8
+    }
+
+    /// Returns a plane of another pixel type with the same dimensions,
+    /// decimation and padding, with each visible pixel set to the value
+    /// returned by `f` for the corresponding pixel of this plane (not padded).
+    pub fn convert_with<U: Pixel>(&self, mut f: impl FnMut(T) -> U) -> Plane<U> {
+        let mut new = Plane::new(
+            self.cfg.width,
+            self.cfg.height,
+            self.cfg.xdec,
+            self.cfg.ydec,
+            self.cfg.xpad,
+            self.cfg.ypad,
+        );
+
+        for (dst, src) in new.rows_iter_mut().zip(self.rows_iter()) {
+            for (dst, &src) in dst.iter_mut().zip(src) {
+                *dst = f(src);
+            }
+        }
+
+        new
+    }
+
+    /// Translates the visible pixels of the plane by `dx` columns and `dy`
+    /// rows, in place. Pixels moved out of the visible area are discarded,
+    /// and the exposed pixels are set to `fill`.
+    ///
+    /// Positive offsets move the content right and down.
+    pub fn translate(&mut self, dx: isize, dy: isize, fill: T) {
+        let width = self.cfg.width;
+        let height = self.cfg.height;
+        let stride = self.cfg.stride;
+        let shift_x = dx.unsigned_abs().min(width);
+        let shift_y = dy.unsigned_abs().min(height);
+        let data = self.data_origin_mut();
+
+        // Rows are copied away from the direction of the shift, so that each
+        // source row is read before being overwritten.
+        for i in 0..height {
+            let (y, src_y) = if dy >= 0 {
+                let y = height - 1 - i;
+                (y, y.checked_sub(shift_y))
+            } else {
+                (i, Some(i + shift_y).filter(|&src_y| src_y < height))
+            };
+
+            match src_y {
+                Some(src_y) if src_y != y => {
+                    data.copy_within(src_y * stride..src_y * stride + width, y * stride);
+                }
+                Some(_) => {}
+                None => data[y * stride..][..width].fill(fill),
+            }
+        }
+
+        for row in data.chunks_mut(stride).take(height) {
+            let row = &mut row[..width];
+            if dx >= 0 {
+                row.copy_within(..width - shift_x, shift_x);
+                row[..shift_x].fill(fill);
+            } else {
+                row.copy_within(shift_x.., 0);
+                row[width - shift_x..].fill(fill);
+            }
+        }
+    }
+
+    /// Iterates over the full rows of the plane buffer, including the
+    /// padding.
+    ///
+    /// Each row has `stride` pixels, starting with the left padding. There are
+    /// `alloc_height` rows: the top padding rows, then the visible rows, then
+    /// the bottom padding rows.
+    pub fn rows_with_padding(&self) -> ChunksExact<'_, T> {
+        let len = self.cfg.stride * self.cfg.alloc_height;
+        self.data[..len].chunks_exact(self.cfg.stride)
+    }
+
+    /// Iterates mutably over the full rows of the plane buffer, including the
+    /// padding.
+    ///
+    /// See [`Plane::rows_with_padding`] for the layout of the rows.
+    pub fn rows_with_padding_mut(&mut self) -> ChunksExactMut<'_, T> {
+        let len = self.cfg.stride * self.cfg.alloc_height;
+        self.data[..len].chunks_exact_mut(self.cfg.stride)
+    }
+
+    /// Iterates over the bytes of the visible pixels of the plane in
+    /// row-major order. Samples wider than a byte are little-endian.
+    pub fn byte_data(&self) -> impl Iterator<Item = u8> + '_ {
+        self.byte_data_with_endianness(Endianness::Little)
+    }
+
+    /// Iterates over the bytes of the visible pixels of the plane in
+    /// row-major order, with samples wider than a byte in the given byte
+    /// order.
+    pub fn byte_data_with_endianness(
+        &self,
+        endianness: Endianness,
+    ) -> impl Iterator<Item = u8> + '_ {
+        let bytewidth = size_of::<T>();
+        let skip = match endianness {
+            Endianness::Little => 0,
+            Endianness::Big => 2 - bytewidth,
+        };
+
+        self.rows_iter().flatten().flat_map(move |&pixel| {
+            let pixel = u16::cast_from(pixel);
+            let bytes = match endianness {
+                Endianness::Little => pixel.to_le_bytes(),
+                Endianness::Big => pixel.to_be_bytes(),
+            };
+            bytes.into_iter().skip(skip).take(bytewidth)
+        })
+    }
+
+    /// Writes the value returned by `f` for each visible pixel of this plane
+    /// into the corresponding visible pixel of `dst`. The padding of `dst` is
+    /// left untouched.
+    ///
+    /// This is the allocation-free counterpart of [`Plane::map_in_place`] for
+    /// double-buffered processing.
+    ///
+    /// # Errors
+    ///
+    /// - If `dst` differs in dimensions from this plane
+    pub fn apply_into(&self, dst: &mut Plane<T>, mut f: impl FnMut(T) -> T) -> Result<(), Error> {
+        self.check_dimensions(dst)?;
+
+        for (src, dst) in self.rows_iter().zip(dst.rows_iter_mut()) {
+            for (&src, dst) in src.iter().zip(dst) {
+                *dst = f(src);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the pixels of the `width` by `height` rectangle at `(x, y)` of
+    /// the visible area to `value`.
+    ///
+    /// # Errors
+    ///
+    /// - If the rectangle extends past the visible area
+    pub fn fill_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        value: T,
+    ) -> Result<(), Error> {
+        let fits = |pos: usize, len: usize, max: usize| {
+            pos.checked_add(len).map_or(false, |end| end <= max)
+        };
+        if !fits(x, width, self.cfg.width) || !fits(y, height, self.cfg.height) {
+            return Err(Error::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+                plane_width: self.cfg.width,
+                plane_height: self.cfg.height,
+            });
+        }
+
+        for row in self.rows_iter_mut().skip(y).take(height) {
+            row[x..x + width].fill(value);
+        }
+
+        Ok(())
+    }
+
+    /// Iterates mutably over the visible rows of the plane along with their
+    /// index.
+    ///
+    /// The iterator has an exact size, so the rows can be preallocated for or
+    /// split into chunks.
+    pub fn indexed_rows_mut(&mut self) -> iter::Enumerate<RowsIterMut<'_, T>> {
+        self.rows_iter_mut().enumerate()
+    }
+
+    /// Copies the visible pixels of the plane into a new vector, in row-major
+    /// order.
+    ///
+    /// Planes without horizontal padding are copied in a single pass,
+    /// others row by row.
+    pub fn to_vec(&self) -> Vec<T> {
+        if let Some(pixels) = self.as_contiguous() {
+            return pixels.to_vec();
+        }
+
+        let mut out = Vec::with_capacity(self.cfg.width * self.cfg.height);
+        for row in self.rows_iter() {
+            out.extend_from_slice(row);
+        }
+
+        out
+    }
+
+    /// Consumes the plane and returns its visible pixels in row-major order.
+    ///
+    /// The buffer of a plane has a larger alignment than a [`Vec`] can be
+    /// deallocated with, so the pixels are copied as in [`Plane::to_vec`]
+    /// even if the plane has no padding.
+    pub fn into_vec(self) -> Vec<T> {
+        self.to_vec()
+    }
+
+    /// Iterates over the visible pixels of column `x`, top to bottom.
+    ///
+    /// Returns `None` if `x` is not below the width of the plane.
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = T> + '_> {
+        if x >= self.cfg.width {
+            return None;
+        }
+
+        Some(
+            self.data_origin()[x..]
+                .iter()
+                .step_by(self.cfg.stride)
+                .take(self.cfg.height)
+                .copied(),
+        )
+    }
+
+    /// Iterates mutably over the visible pixels of column `x`, top to bottom.
+    ///
+    /// Returns `None` if `x` is not below the width of the plane.
+    pub fn column_mut(&mut self, x: usize) -> Option<impl Iterator<Item = &mut T> + '_> {
+        if x >= self.cfg.width {
+            return None;
+        }
+
+        let stride = self.cfg.stride;
+        let height = self.cfg.height;
+        Some(
+            self.data_origin_mut()[x..]
+                .iter_mut()
+                .step_by(stride)
+                .take(height),
+        )
+    }
+
+    /// Returns the value of the visible pixels if they are all equal, or
+    /// `None` if they differ or the plane is empty.
+    ///
+    /// The scan stops at the first row that differs.
+    pub fn is_uniform(&self) -> Option<T> {
+        let mut rows = self.rows_iter();
+        let first_row = rows.next()?;
+        let value = *first_row.first()?;
+
+        if first_row.iter().all(|&pixel| pixel == value) && rows.all(|row| row == first_row) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the buffer of the plane with `data`, keeping the
+    /// configuration, and returns the previous buffer.
+    ///
+    /// This allows rotating buffers between planes of the same layout, such as
+    /// decode targets, without reallocating. `data` must cover the whole
+    /// allocation of the plane, including the padding.
+    ///
+    /// # Errors
+    ///
+    /// - If the length of `data` is not `stride * alloc_height`
+    pub fn replace_data(&mut self, data: PlaneData<T>) -> Result<PlaneData<T>, Error> {
+        let expected = self.cfg.stride * self.cfg.alloc_height;
+        if data.len() != expected {
+            return Err(Error::DataLength {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        Ok(core::mem::replace(&mut self.data, data))
+    }
+
+    /// Fills everything around the visible pixels of the plane, i.e. the
+    /// padding and the alignment slack on every side including the corners,
+    /// with `value`.
+    ///
+    /// Unlike [`Plane::pad`], which replicates the edge pixels, this gives the
+    /// border a known value such as black or zero. The visible pixels are left
+    /// untouched.
+    pub fn pad_constant(&mut self, value: T) {
+        let PlaneConfig {
+            stride,
+            width,
+            height,
+            xorigin,
+            yorigin,
+            ..
+        } = self.cfg;
+        if stride == 0 {
+            return;
+        }
+
+        for (y, row) in self.data.chunks_exact_mut(stride).enumerate() {
+            if (yorigin..yorigin + height).contains(&y) {
+                row[..xorigin].fill(value);
+                row[xorigin + width..].fill(value);
+            } else {
+                row.fill(value);
+            }
+        }
+    }
+
+    /// Returns a copy of the plane with `xpad` padding pixels on the left and
+    /// right and `ypad` on the top and bottom, filled according to `fill`.
+    ///
+    /// The visible pixels and the decimation are kept, so this can both grow
+    /// the padding for a filter and drop it to save memory.
+    pub fn with_padding(&self, xpad: usize, ypad: usize, fill: PaddingFill<T>) -> Plane<T> {
+        let mut new = Plane::new(
+            self.cfg.width,
+            self.cfg.height,
+            self.cfg.xdec,
+            self.cfg.ydec,
+            xpad,
+            ypad,
+        );
+        for (dst, src) in new.rows_iter_mut().zip(self.rows_iter()) {
+            dst.copy_from_slice(src);
+        }
+
+        match fill {
+            PaddingFill::Replicate => new.pad(
+                self.cfg.width << self.cfg.xdec,
+                self.cfg.height << self.cfg.ydec,
+            ),
+            PaddingFill::Constant(value) => new.pad_constant(value),
+        }
+
+        new
+    }
+
+    /// Reduces each visible row of the plane to a single value, starting from
+    /// `init()` and combining it with the row using `f`.
+    ///
+    /// This covers per-row statistics such as sums or maxima without
+    /// iterating over the rows by hand.
+    pub fn fold_rows<A>(&self, init: impl Fn() -> A, mut f: impl FnMut(A, &[T]) -> A) -> Vec<A> {
+        self.rows_iter().map(|row| f(init(), row)).collect()
+    }
+
+    /// Returns the memory layout of the plane, for diagnosing padding and
+    /// alignment issues.
+    pub fn layout(&self) -> PlaneLayout {
+        let cfg = &self.cfg;
+        PlaneLayout {
+            width: cfg.width,
+            height: cfg.height,
+            stride: cfg.stride,
+            pad_left: cfg.xorigin,
+            pad_right: cfg.stride.saturating_sub(cfg.xorigin + cfg.width),
+            pad_top: cfg.yorigin,
+            pad_bottom: cfg.alloc_height.saturating_sub(cfg.yorigin + cfg.height),
+            len: self.data.len(),
+            origin: cfg.yorigin * cfg.stride + cfg.xorigin,
+        }
+    }
+
+    /// Iterates over every `step`-th visible row of the plane, starting with
+    /// the first one.
+    ///
+    /// The skipped rows are not visited, so this is cheap for previews and
+    /// histograms of large planes.
+    pub fn rows_step(&self, step: NonZeroUsize) -> impl Iterator<Item = &[T]> + '_ {
+        let stride = self.cfg.stride;
+        let width = self.cfg.width;
+        let origin = self.index(0, 0);
+
+        (0..self.cfg.height)
+            .step_by(step.get())
+            .map(move |y| &self.data[origin + y * stride..][..width])
+    }
+
+    /// Iterates over the visible pixels of every `row_step`-th row and every
+    /// `col_step`-th column of the plane, in row-major order.
+    pub fn pixels_step(
+        &self,
+        row_step: NonZeroUsize,
+        col_step: NonZeroUsize,
+    ) -> impl Iterator<Item = T> + '_ {
+        self.rows_step(row_step)
+            .flat_map(move |row| row.iter().step_by(col_step.get()).copied())
+    }
+
+    /// Returns the plane convolved with the 3x3 `kernel`, given in row-major
+    /// order (not padded).
+    ///
+    /// Each output pixel is `(sum + offset) / divisor`, where `sum` is the
+    /// weighted sum of the 3x3 neighborhood, clamped to the range of
+    /// `bit_depth`. Pixels outside the visible area are replaced by the
+    /// nearest edge pixel.
+    ///
+    /// # Panics
+    ///
+    /// - If `divisor` is zero
+    pub fn convolve_3x3(
+        &self,
+        kernel: &[i32; 9],
+        divisor: i32,
+        offset: i32,
+        bit_depth: usize,
+    ) -> Plane<T> {
+        assert!(divisor != 0, "divisor must not be zero");
+
+        let width = self.cfg.width;
+        let height = self.cfg.height;
+        let max = u32::cast_from(T::max_for_depth(bit_depth)) as i32;
+        let mut new = Plane::new(width, height, self.cfg.xdec, self.cfg.ydec, 0, 0);
+
+        for (y, dst_row) in new.rows_iter_mut().enumerate() {
+            let rows = [y.saturating_sub(1), y, (y + 1).min(height - 1)].map(|y| &self[y]);
+            for (x, dst) in dst_row.iter_mut().enumerate() {
+                let cols = [x.saturating_sub(1), x, (x + 1).min(width - 1)];
+                let mut sum = 0;
+                for (row, weights) in rows.iter().zip(kernel.chunks_exact(3)) {
+                    for (&col, &weight) in cols.iter().zip(weights) {
+                        sum += weight * u32::cast_from(row[col]) as i32;
+                    }
+                }
+                *dst = T::cast_from(((sum + offset) / divisor).clamp(0, max) as u32);
+            }
+        }
+
+        new
+    }
+
+    /// Returns the visible pixel nearest to `(x, y)`, clamping the coordinates
+    /// to the visible area.
+    ///
+    /// This is the clamp-to-edge addressing used by filters sampling around
+    /// the borders, and never reads the padding.
+    ///
+    /// # Panics
+    ///
+    /// - If the plane has no visible pixels
+    pub fn pixel_clamped(&self, x: isize, y: isize) -> T {
+        assert!(
+            self.cfg.width > 0 && self.cfg.height > 0,
+            "plane has no visible pixels"
+        );
+
+        let clamp = |v: isize, len: usize| usize::try_from(v).map_or(0, |v| v.min(len - 1));
+        self.p(clamp(x, self.cfg.width), clamp(y, self.cfg.height))
+    }
+
+    /// Returns the visible pixels of the plane as bytes, without copying, if
+    /// they are contiguous in memory, see [`Plane::as_contiguous`].
+    ///
+    /// Samples wider than a byte are in native byte order, unlike
+    /// [`Plane::byte_data`] which is always little-endian.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        let pixels = self.as_contiguous()?;
+
+        // SAFETY: the pixels are plain integers, so any pixel is valid as
+        // bytes and the slice covers exactly the memory of the pixels
+        Some(unsafe {
+            core::slice::from_raw_parts(pixels.as_ptr().cast(), core::mem::size_of_val(pixels))
+        })
+    }
+
+    /// Mutable version of [`Plane::as_bytes`].
+    pub fn as_bytes_mut(&mut self) -> Option<&mut [u8]> {
+        if self.cfg.stride != self.cfg.width {
+            return None;
+        }
+        let len = self.cfg.width * self.cfg.height;
+        let pixels = &mut self.data_origin_mut()[..len];
+
+        // SAFETY: the pixels are plain integers, so any byte pattern is a
+        // valid pixel and the slice covers exactly the memory of the pixels
+        Some(unsafe {
+            core::slice::from_raw_parts_mut(
+                pixels.as_mut_ptr().cast(),
+                core::mem::size_of_val(pixels),
+            )
+        })
+    }
+
+    /// Returns the plane with each pixel replaced by the median of its 3x3
+    /// neighborhood (not padded), which removes impulse noise.
+    ///
+    /// Pixels outside the visible area are replaced by the nearest edge pixel.
+    pub fn median_3x3(&self) -> Plane<T> {
+        // Median selection network for 9 elements, from "Fast median search:
+        // an ANSI C implementation" by N. Devillard
+        const NETWORK: [(usize, usize); 19] = [
+            (1, 2),
+            (4, 5),
+            (7, 8),
+            (0, 1),
+            (3, 4),
+            (6, 7),
+            (1, 2),
+            (4, 5),
+            (7, 8),
+            (0, 3),
+            (5, 8),
+            (4, 7),
+            (3, 6),
+            (1, 4),
+            (2, 5),
+            (4, 7),
+            (4, 2),
+            (6, 4),
+            (4, 2),
+        ];
+
+        let width = self.cfg.width;
+        let height = self.cfg.height;
+        let mut new = Plane::new(width, height, self.cfg.xdec, self.cfg.ydec, 0, 0);
+
+        for (y, dst_row) in new.rows_iter_mut().enumerate() {
+            let rows = [y.saturating_sub(1), y, (y + 1).min(height - 1)].map(|y| &self[y]);
+            for (x, dst) in dst_row.iter_mut().enumerate() {
+                let cols = [x.saturating_sub(1), x, (x + 1).min(width - 1)];
+                let mut p = [T::cast_from(0); 9];
+                for (p, row) in p.chunks_exact_mut(3).zip(rows) {
+                    for (p, &col) in p.iter_mut().zip(&cols) {
+                        *p = row[col];
+                    }
+                }
+
+                for (a, b) in NETWORK {
+                    if p[a] > p[b] {
+                        p.swap(a, b);
+                    }
+                }
+                *dst = p[4];
+            }
+        }
+
+        new
+    }
+
+    /// Returns a mask of the plane (not padded), with each pixel set to
+    /// `above` if the source pixel is at least `threshold` and to `below`
+    /// otherwise.
+    pub fn threshold(&self, threshold: T, above: T, below: T) -> Plane<T> {
+        let mut new = Plane::new(
+            self.cfg.width,
+            self.cfg.height,
+            self.cfg.xdec,
+            self.cfg.ydec,
+            0,
+            0,
+        );
+        for (dst, src) in new.rows_iter_mut().zip(self.rows_iter()) {
+            for (dst, &src) in dst.iter_mut().zip(src) {
+                *dst = if src >= threshold { above } else { below };
+            }
+        }
+
+        new
+    }
+
+    /// In-place version of [`Plane::threshold`]. The padding is left
+    /// untouched.
+    pub fn threshold_in_place(&mut self, threshold: T, above: T, below: T) {
+        self.map_in_place(|pixel| if pixel >= threshold { above } else { below });
+    }
+}
+
+/// Read access to the visible pixels of a plane-like buffer.
+///
+/// This allows writing algorithms that are generic over the buffer they read
+/// from rather than tied to [`Plane`].
+pub trait PlaneExt<T: Pixel> {
+    /// Returns the width of the visible area.
+    fn width(&self) -> usize;
+
+    /// Returns the height of the visible area.
+    fn height(&self) -> usize;
+
+    /// Returns the visible pixels of row `y`.
+    ///
+    /// # Panics
+    ///
+    /// - If `y` is not a visible row
+    fn visible_row(&self, y: usize) -> &[T];
+
+    /// Returns the pixel at the given visible coordinates.
+    ///
+    /// # Panics
+    ///
+    /// - If the coordinates are outside of the visible area
+    fn pixel(&self, x: usize, y: usize) -> T {
+        self.visible_row(y)[x]
+    }
+}
+
+/// Write access to the visible pixels of a plane-like buffer.
+pub trait PlaneMutExt<T: Pixel>: PlaneExt<T> {
+    /// Returns the visible pixels of row `y`, mutably.
+    ///
+    /// # Panics
+    ///
+    /// - If `y` is not a visible row
+    fn visible_row_mut(&mut self, y: usize) -> &mut [T];
+
+    /// Returns the pixel at the given visible coordinates, mutably.
+    ///
+    /// # Panics
+    ///
+    /// - If the coordinates are outside of the visible area
+    fn pixel_mut(&mut self, x: usize, y: usize) -> &mut T {
+        &mut self.visible_row_mut(y)[x]
+    }
+}
+
+impl<T: Pixel> PlaneExt<T> for Plane<T> {
+    fn width(&self) -> usize {
+        self.cfg.width
+    }
+
+    fn height(&self) -> usize {
+        self.cfg.height
+    }
+
+    fn visible_row(&self, y: usize) -> &[T] {
+        &self[y]
+    }
+}
+
+impl<T: Pixel> PlaneMutExt<T> for Plane<T> {
+    fn visible_row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self[y]
+    }
+}
+
+impl<'a, T: Pixel> TryFrom<&'a Plane<T>> for &'a [T] {
+    type Error = Error;
+
+    fn try_from(plane: &'a Plane<T>) -> Result<Self, Error> {
+        plane.try_as_slice()
+    }
+}
+
+/// Returns the visible pixels of a line of the plane.
+///
+/// # Panics
+///
+/// - If the line is outside of the visible area
+impl<T: Pixel> Index<usize> for Plane<T> {
+    type Output = [T];
+    fn index(&self, y: usize) -> &Self::Output {
+        assert!(
+            y < self.cfg.height,
+            "row index {} out of range for plane of height {}",
+            y,
+            self.cfg.height
+        );
+        let range = self.row_range_cropped(0, y as isize);
+        &self.data[range]
+    }
+}
+
+/// Returns the visible pixels of a line of the plane, mutably.
+///
+/// # Panics
+///
+/// - If the line is outside of the visible area
+impl<T: Pixel> IndexMut<usize> for Plane<T> {
+    fn index_mut(&mut self, y: usize) -> &mut Self::Output {
+        assert!(
+            y < self.cfg.height,
+            "row index {} out of range for plane of height {}",
+            y,
+            self.cfg.height
+        );
+        let range = self.row_range_cropped(0, y as isize);
+        &mut self.data[range]
+    }
+}
+
+/// Returns the pixel at `(x, y)`.
+///
+/// # Panics
+///
+/// - If the coordinates are outside of the visible area
+impl<T: Pixel> Index<(usize, usize)> for Plane<T> {
+    type Output = T;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self[y][x]
+    }
+}
+
+/// Returns the pixel at `(x, y)`, mutably.
+///
+/// # Panics
+///
+/// - If the coordinates are outside of the visible area
+impl<T: Pixel> IndexMut<(usize, usize)> for Plane<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        &mut self[y][x]
+    }
+}
+
+/// Iterator over plane pixels, skipping padding.
+#[derive(Clone, Debug)]
+pub struct PlaneIter<'a, T: Pixel> {
+    plane: &'a Plane<T>,
+    // Indices of the next pixels from the front and past the back, in
+    // row-major order over the visible pixels
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T: Pixel> PlaneIter<'a, T> {
+    /// Creates a new iterator.
+    pub fn new(plane: &'a Plane<T>) -> Self {
+        Self {
+            plane,
+            front: 0,
+            back: plane.cfg.width * plane.cfg.height,
+        }
+    }
+
+    fn pixel(&self, index: usize) -> T {
+        let width = self.plane.cfg.width;
+        self.plane.p(index % width, index / width)
+    }
+}
+
+impl<'a, T: Pixel> Iterator for PlaneIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let pixel = self.pixel(self.front);
+        self.front += 1;
+        Some(pixel)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Pixel> DoubleEndedIterator for PlaneIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.pixel(self.back))
+    }
+}
+
+impl<T: Pixel> ExactSizeIterator for PlaneIter<'_, T> {}
+impl<T: Pixel> FusedIterator for PlaneIter<'_, T> {}
+
+// A Plane, PlaneSlice, or PlaneRegion is assumed to include or be able to include
+// padding on the edge of the frame
+#[derive(Clone, Copy, Debug)]
+pub struct PlaneSlice<'a, T: Pixel> {
+    pub plane: &'a Plane<T>,
+    pub x: isize,
+    pub y: isize,
+}
+
+// A RowsIter or RowsIterMut is assumed to crop the padding from the frame edges
+#[derive(Clone, Debug)]
+pub struct RowsIter<'a, T: Pixel> {
+    plane: &'a Plane<T>,
+    x: isize,
+    y: isize,
+    end: isize,
+}
+
+impl<'a, T: Pixel> Iterator for RowsIter<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end > self.y {
+            // cannot directly return self.ps.row(row) due to lifetime issue
+            let range = self.plane.row_range_cropped(self.x, self.y);
+            self.y += 1;
+            Some(&self.plane.data[range])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.y;
+        debug_assert!(remaining >= 0);
+        let remaining = remaining as usize;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Pixel> DoubleEndedIterator for RowsIter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end > self.y {
+            self.end -= 1;
+            let range = self.plane.row_range_cropped(self.x, self.end);
+            Some(&self.plane.data[range])
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: Pixel> ExactSizeIterator for RowsIter<'a, T> {}
+impl<'a, T: Pixel> FusedIterator for RowsIter<'a, T> {}
+
+impl<'a, T: Pixel> PlaneSlice<'a, T> {
+    #[allow(unused)]
+    pub fn as_ptr(&self) -> *const T {
+        self[0].as_ptr()
+    }
+
+    pub fn rows_iter(&self) -> RowsIter<'_, T> {
+        RowsIter {
+            plane: self.plane,
+            x: self.x,
+            y: self.y,
+            end: self.plane.cfg.height as isize,
+        }
+    }
+
+    pub fn clamp(&self) -> PlaneSlice<'a, T> {
+        PlaneSlice {
+            plane: self.plane,
+            x: self.x.clamp(
+                -(self.plane.cfg.xorigin as isize),
+                self.plane.cfg.width as isize,
+            ),
+            y: self.y.clamp(
+                -(self.plane.cfg.yorigin as isize),
+                self.plane.cfg.height as isize,
+            ),
+        }
+    }
+
+    pub fn subslice(&self, xo: usize, yo: usize) -> PlaneSlice<'a, T> {
+        PlaneSlice {
+            plane: self.plane,
+            x: self.x + xo as isize,
+            y: self.y + yo as isize,
+        }
+    }
+
+    pub fn reslice(&self, xo: isize, yo: isize) -> PlaneSlice<'a, T> {
+        PlaneSlice {
+            plane: self.plane,
+            x: self.x + xo,
+            y: self.y + yo,
+        }
+    }
+
+    /// A slice starting i pixels above the current one.
+    pub fn go_up(&self, i: usize) -> PlaneSlice<'a, T> {
+        PlaneSlice {
+            plane: self.plane,
+            x: self.x,
+            y: self.y - i as isize,
+        }
+    }
+
+    /// A slice starting i pixels to the left of the current one.
+    pub fn go_left(&self, i: usize) -> PlaneSlice<'a, T> {
+        PlaneSlice {
+            plane: self.plane,
+            x: self.x - i as isize,
+            y: self.y,
+        }
+    }
+
+    pub fn p(&self, add_x: usize, add_y: usize) -> T {
+        let new_y = (self.y + add_y as isize + self.plane.cfg.yorigin as isize) as usize;
+        let new_x = (self.x + add_x as isize + self.plane.cfg.xorigin as isize) as usize;
+        self.plane.data[new_y * self.plane.cfg.stride + new_x]
+    }
+
+    /// Checks if `add_y` and `add_x` lies in the allocated bounds of the
+    /// underlying plane.
+    pub fn accessible(&self, add_x: usize, add_y: usize) -> bool {
+        let y = (self.y + add_y as isize + self.plane.cfg.yorigin as isize) as usize;
+        let x = (self.x + add_x as isize + self.plane.cfg.xorigin as isize) as usize;
+        y < self.plane.cfg.alloc_height && x < self.plane.cfg.stride
+    }
+
+    /// Checks if -`sub_x` and -`sub_y` lies in the allocated bounds of the
+    /// underlying plane.
+    pub fn accessible_neg(&self, sub_x: usize, sub_y: usize) -> bool {
+        let y = self.y - sub_y as isize + self.plane.cfg.yorigin as isize;
+        let x = self.x - sub_x as isize + self.plane.cfg.xorigin as isize;
+        y >= 0 && x >= 0
+    }
+
+    /// This version of the function crops off the padding on the right side of the image
+    pub fn row_cropped(&self, y: usize) -> &[T] {
+        let y = (self.y + y as isize + self.plane.cfg.yorigin as isize) as usize;
+        let x = (self.x + self.plane.cfg.xorigin as isize) as usize;
+        let start = y * self.plane.cfg.stride + x;
+        let width = (self.plane.cfg.width as isize - self.x) as usize;
+        &self.plane.data[start..start + width]
+    }
+
+    /// This version of the function includes the padding on the right side of the image
+    pub fn row(&self, y: usize) -> &[T] {
+        let y = (self.y + y as isize + self.plane.cfg.yorigin as isize) as usize;
+        let x = (self.x + self.plane.cfg.xorigin as isize) as usize;
+        let start = y * self.plane.cfg.stride + x;
+        let width = self.plane.cfg.stride - x;
+        &self.plane.data[start..start + width]
+    }
+}
+
+impl<'a, T: Pixel> Index<usize> for PlaneSlice<'a, T> {
+    type Output = [T];
+    fn index(&self, index: usize) -> &Self::Output {
+        let range = self.plane.row_range(self.x, self.y + index as isize);
+        &self.plane.data[range]
+    }
+}
+
+pub struct PlaneMutSlice<'a, T: Pixel> {
+    pub plane: &'a mut Plane<T>,
+    pub x: isize,
+    pub y: isize,
+}
+
+pub struct RowsIterMut<'a, T: Pixel> {
+    plane: *mut Plane<T>,
+    x: isize,
+    y: isize,
+    end: isize,
+    phantom: PhantomData<&'a mut Plane<T>>,
+}
+
+impl<'a, T: Pixel> Iterator for RowsIterMut<'a, T> {
+    type Item = &'a mut [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: there could not be a concurrent call using a mutable reference to the plane
+        let plane = unsafe { &mut *self.plane };
+        if self.end > self.y {
+            // cannot directly return self.ps.row(row) due to lifetime issue
+            let range = plane.row_range_cropped(self.x, self.y);
+            self.y += 1;
+            Some(&mut plane.data[range])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.y;
+        debug_assert!(remaining >= 0);
+        let remaining = remaining as usize;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Pixel> DoubleEndedIterator for RowsIterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // SAFETY: there could not be a concurrent call using a mutable reference to the plane
+        let plane = unsafe { &mut *self.plane };
+        if self.end > self.y {
+            self.end -= 1;
+            let range = plane.row_range_cropped(self.x, self.end);
+            Some(&mut plane.data[range])
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: Pixel> ExactSizeIterator for RowsIterMut<'a, T> {}
+impl<'a, T: Pixel> FusedIterator for RowsIterMut<'a, T> {}
+
+impl<'a, T: Pixel> PlaneMutSlice<'a, T> {
+    #[allow(unused)]
+    pub fn rows_iter(&self) -> RowsIter<'_, T> {
+        RowsIter {
+            plane: self.plane,
+            x: self.x,
+            y: self.y,
+            end: self.plane.cfg.height as isize,
+        }
+    }
+
+    pub fn rows_iter_mut(&mut self) -> RowsIterMut<'_, T> {
+        RowsIterMut {
+            plane: self.plane as *mut Plane<T>,
+            x: self.x,
+            y: self.y,
+            end: self.plane.cfg.height as isize,
+            phantom: PhantomData,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn subslice(&mut self, xo: usize, yo: usize) -> PlaneMutSlice<'_, T> {
+        PlaneMutSlice {
+            plane: self.plane,
+            x: self.x + xo as isize,
+            y: self.y + yo as isize,
+        }
+    }
+}
+
+impl<'a, T: Pixel> Index<usize> for PlaneMutSlice<'a, T> {
+    type Output = [T];
+    fn index(&self, index: usize) -> &Self::Output {
+        let range = self.plane.row_range(self.x, self.y + index as isize);
+        &self.plane.data[range]
+    }
+}
+
+impl<'a, T: Pixel> IndexMut<usize> for PlaneMutSlice<'a, T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let range = self.plane.row_range(self.x, self.y + index as isize);
+        &mut self.plane.data[range]
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    use wasm_bindgen_test::*;
+
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn copy_from_raw_u8() {
+        #[rustfmt::skip]
+        let mut plane = Plane::from_slice(&[
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 2, 3, 4, 0, 0,
+            0, 0, 8, 7, 6, 5, 0, 0,
+            0, 0, 9, 8, 7, 6, 0, 0,
+            0, 0, 2, 3, 4, 5, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ], 8);
+
+        let input = vec![42u8; 64];
+
+        plane.copy_from_raw_u8(&input, 8, 1);
+
+        println!("{:?}", &plane.data[..10]);
+
+        assert_eq!(&input[..64], &plane.data[..64]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn copy_from_raw_u8_u16_endianness() {
+        let mut plane = Plane::from_slice(&[0u16; 8], 4);
+
+        #[rustfmt::skip]
+        let input = [
+            0x01, 0x02, 0x03, 0x04, 0xff, 0x03, 0x00, 0x00,
+            0x00, 0x01, 0x02, 0x00, 0x34, 0x12, 0xaa, 0xbb,
+        ];
+
+        plane.copy_from_raw_u8(&input, 8, 2);
+        assert_eq!(
+            &[0x0201, 0x0403, 0x03ff, 0x0000, 0x0100, 0x0002, 0x1234, 0xbbaa][..],
+            &plane.data[..]
+        );
+
+        plane.copy_from_raw_u8_with_endianness(&input, 8, 2, Endianness::Big);
+        assert_eq!(
+            &[0x0102, 0x0304, 0xff03, 0x0000, 0x0001, 0x0200, 0x3412, 0xaabb][..],
+            &plane.data[..]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn copy_to_raw_u8() {
+        #[rustfmt::skip]
+        let plane = Plane::from_slice(&[
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 2, 3, 4, 0, 0,
+            0, 0, 8, 7, 6, 5, 0, 0,
+            0, 0, 9, 8, 7, 6, 0, 0,
+            0, 0, 2, 3, 4, 5, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ], 8);
+
+        let mut output = vec![42u8; 64];
+
+        plane.copy_to_raw_u8(&mut output, 8, 1);
+
+        println!("{:?}", &plane.data[..10]);
+
+        assert_eq!(&output[..64], &plane.data[..64]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downsample() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 1, 2, 3, 4, 0, 0,
+                0, 0, 8, 7, 6, 5, 0, 0,
+                0, 0, 9, 8, 7, 6, 0, 0,
+                0, 0, 2, 3, 4, 5, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 9,
+                width: 4,
+                height: 4,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+        let downsampled = plane.downsampled(4, 4);
+
+        #[rustfmt::skip]
+        let expected = &[
+            5, 5,
+            6, 6,
+        ];
+
+        let v: Vec<_> = downsampled.iter().collect();
+
+        assert_eq!(&expected[..], &v[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downsample_odd() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 1, 2, 3, 4, 0, 0,
+                0, 0, 8, 7, 6, 5, 0, 0,
+                0, 0, 9, 8, 7, 6, 0, 0,
+                0, 0, 2, 3, 4, 5, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 9,
+                width: 3,
+                height: 3,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+        let downsampled = plane.downsampled(3, 3);
+
+        #[rustfmt::skip]
+        let expected = &[
+            5, 5,
+            6, 6,
+        ];
+
+        let v: Vec<_> = downsampled.iter().collect();
+        assert_eq!(&expected[..], &v[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downscale() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 1, 4, 5, 0, 0,
+                0, 0, 2, 3, 6, 7, 0, 0,
+                0, 0, 8, 9, 7, 5, 0, 0,
+                0, 0, 9, 8, 3, 1, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 9,
+                width: 4,
+                height: 4,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+        let downscaled = plane.downscale::<2>();
+
+        #[rustfmt::skip]
+        let expected = &[
+            2, 6,
+            9, 4
+        ];
+
+        let v: Vec<_> = downscaled.iter().collect();
+        assert_eq!(&expected[..], &v[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downscale_odd() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                1, 2, 3, 4, 1, 2, 3, 4,
+                0, 0, 8, 7, 6, 5, 8, 7,
+                6, 5, 8, 7, 6, 5, 8, 7,
+                6, 5, 8, 7, 0, 0, 2, 3,
+                4, 5, 0, 0, 9, 8, 7, 6,
+                0, 0, 0, 0, 2, 3, 4, 5,
+                0, 0, 0, 0, 2, 3, 4, 5,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 7,
+                width: 8,
+                height: 7,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 0,
+                yorigin: 0,
+            },
+        };
+
+        let downscaled = plane.downscale::<3>();
+
+        #[rustfmt::skip]
+        let expected = &[
+            4, 5,
+            3, 3
+        ];
+
+        let v: Vec<_> = downscaled.iter().collect();
+        assert_eq!(&expected[..], &v[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downscale_odd_2() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                9, 8, 3, 1, 0, 1, 4, 5, 0, 0,
+                0, 1, 4, 5, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0, 9, 0,
+                0, 2, 3, 6, 7, 0, 0, 0, 0, 0,
+                0, 0, 8, 9, 7, 5, 0, 0, 0, 0,
+                9, 8, 3, 1, 0, 1, 4, 5, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 2, 3, 6, 7, 0,
+                0, 0, 0, 0, 0, 0, 8, 9, 7, 5,
+                0, 0, 0, 0, 9, 8, 3, 1, 0, 0
+            ]),
+            cfg: PlaneConfig {
+                stride: 10,
+                alloc_height: 10,
+                width: 10,
+                height: 10,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 0,
+                yorigin: 0,
+            },
+        };
+        let downscaled = plane.downscale::<3>();
+
+        #[rustfmt::skip]
+        let expected = &[
+            3, 1, 2,
+            4, 4, 1,
+            0, 0, 4,
+        ];
+
+        let v: Vec<_> = downscaled.iter().collect();
+        assert_eq!(&expected[..], &v[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_pad() {
+        #[rustfmt::skip]
+        let mut plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 1, 2, 3, 4, 0, 0,
+                0, 0, 8, 7, 6, 5, 0, 0,
+                0, 0, 9, 8, 7, 6, 0, 0,
+                0, 0, 2, 3, 4, 5, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 9,
+                width: 4,
+                height: 4,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+        plane.pad(4, 4);
+
         #[rustfmt::skip]
-        let plane = Plane::from_slice(&[
+        assert_eq!(&[
+            1, 1, 1, 2, 3, 4, 4, 4,
+            1, 1, 1, 2, 3, 4, 4, 4,
+            1, 1, 1, 2, 3, 4, 4, 4,
+            1, 1, 1, 2, 3, 4, 4, 4,
+            8, 8, 8, 7, 6, 5, 5, 5,
+            9, 9, 9, 8, 7, 6, 6, 6,
+            2, 2, 2, 3, 4, 5, 5, 5,
+            2, 2, 2, 3, 4, 5, 5, 5,
+            2, 2, 2, 3, 4, 5, 5, 5,
+        ], &plane.data[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_pixel_iterator() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 1, 2, 3, 4, 0, 0,
+                0, 0, 8, 7, 6, 5, 0, 0,
+                0, 0, 9, 8, 7, 6, 0, 0,
+                0, 0, 2, 3, 4, 5, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 9,
+                width: 4,
+                height: 4,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+
+        let pixels: Vec<u8> = plane.iter().collect();
+
+        assert_eq!(
+            &[1, 2, 3, 4, 8, 7, 6, 5, 9, 8, 7, 6, 2, 3, 4, 5,][..],
+            &pixels[..]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_sse_sad() {
+        let a = Plane::from_slice(&[0u16, 1, 2, 3, 1023, 1023], 3);
+        let b = Plane::from_slice(&[1u16, 1, 0, 3, 0, 1023], 3);
+
+        assert_eq!(a.sse(&b), Ok(1 + 4 + 1023 * 1023));
+        assert_eq!(a.sse(&a), Ok(0));
+        assert_eq!(a.sad(&b), Ok(1 + 2 + 1023));
+        assert_eq!(a.sad(&a), Ok(0));
+
+        let c = Plane::from_slice(&[0u16; 6], 2);
+        assert_eq!(
+            a.sse(&c),
+            Err(Error::DimensionMismatch {
+                expected: (3, 2),
+                found: (2, 3),
+            })
+        );
+        assert!(a.sad(&c).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_rotate() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 1, 2, 3, 0, 0, 0,
+                0, 0, 4, 5, 6, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 6,
+                width: 3,
+                height: 2,
+                xdec: 1,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+
+        let transposed = plane.transpose();
+        assert_eq!((transposed.cfg.width, transposed.cfg.height), (2, 3));
+        assert_eq!((transposed.cfg.xdec, transposed.cfg.ydec), (0, 1));
+        assert_eq!(
+            &[1, 4, 2, 5, 3, 6][..],
+            &transposed.iter().collect::<Vec<_>>()[..]
+        );
+
+        let cw = plane.rotate_90_cw();
+        assert_eq!(&[4, 1, 5, 2, 6, 3][..], &cw.iter().collect::<Vec<_>>()[..]);
+
+        let ccw = plane.rotate_90_ccw();
+        assert_eq!(&[3, 6, 2, 5, 1, 4][..], &ccw.iter().collect::<Vec<_>>()[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_flip() {
+        #[rustfmt::skip]
+        let mut plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 1, 2, 3, 4, 0, 0,
+                0, 0, 8, 7, 6, 5, 0, 0,
+                0, 0, 9, 8, 7, 6, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 8,
+                alloc_height: 7,
+                width: 4,
+                height: 3,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 2,
+                yorigin: 3,
+            },
+        };
+
+        plane.flip_horizontal();
+        #[rustfmt::skip]
+        assert_eq!(&[
             0, 0, 0, 0, 0, 0, 0, 0,
             0, 0, 0, 0, 0, 0, 0, 0,
             0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 1, 2, 3, 4, 0, 0,
-            0, 0, 8, 7, 6, 5, 0, 0,
-            0, 0, 9, 8, 7, 6, 0, 0,
-            0, 0, 2, 3, 4, 5, 0, 0,
+            0, 0, 4, 3, 2, 1, 0, 0,
+            0, 0, 5, 6, 7, 8, 0, 0,
+            0, 0, 6, 7, 8, 9, 0, 0,
             0, 0, 0, 0, 0, 0, 0, 0,
+        ], &plane.data[..]);
+
+        plane.flip_vertical();
+        #[rustfmt::skip]
+        assert_eq!(&[
             0, 0, 0, 0, 0, 0, 0, 0,
-        ], 8);
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 6, 7, 8, 9, 0, 0,
+            0, 0, 5, 6, 7, 8, 0, 0,
+            0, 0, 4, 3, 2, 1, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+        ], &plane.data[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_rows_range() {
+        #[rustfmt::skip]
+        let mut plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0,
+                0, 1, 2, 3, 0, 0,
+                0, 4, 5, 6, 0, 0,
+                0, 7, 8, 9, 0, 0,
+                0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 6,
+                alloc_height: 5,
+                width: 3,
+                height: 3,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 1,
+                yorigin: 1,
+            },
+        };
+
+        let rows = plane.rows_range(1, 10);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows.collect::<Vec<_>>(), [&[4, 5, 6], &[7, 8, 9]]);
+
+        assert_eq!(plane.rows_range(0, 1).collect::<Vec<_>>(), [&[1, 2, 3]]);
+        assert_eq!(plane.rows_range(2, 1).len(), 0);
+        assert_eq!(plane.rows_range(3, 5).count(), 0);
+        assert_eq!(plane.rows_range(7, 9).len(), 0);
+
+        for row in plane.rows_range_mut(1, 2) {
+            row.fill(0);
+        }
+        let pixels: Vec<u8> = plane.iter().collect();
+        assert_eq!(&[1, 2, 3, 0, 0, 0, 7, 8, 9][..], &pixels[..]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_pixel_unchecked() {
+        let mut plane = Plane::<u16>::new(4, 4, 0, 0, 8, 8);
+        let stride = plane.cfg.stride;
+        plane.data_origin_mut()[stride + 2] = 1023;
+
+        // SAFETY: (2, 1) is inside the visible area
+        unsafe {
+            assert_eq!(plane.pixel_unchecked(2, 1), 1023);
+            *plane.pixel_unchecked_mut(3, 3) = 7;
+        }
+        assert_eq!(plane.p(3, 3), 7);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_statistics() {
+        #[rustfmt::skip]
+        let plane = Plane::<u16> {
+            data: PlaneData::from_slice(&[
+                9, 9, 9, 9,
+                9, 1, 2, 9,
+                9, 3, 3, 9,
+                9, 9, 9, 9,
+            ]),
+            cfg: PlaneConfig {
+                stride: 4,
+                alloc_height: 4,
+                width: 2,
+                height: 2,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 1,
+                yorigin: 1,
+            },
+        };
+
+        assert_eq!(plane.sum(), 9);
+        assert!((plane.mean() - 2.25).abs() < f64::EPSILON);
+        assert_eq!(plane.min(), Some(1));
+        assert_eq!(plane.max(), Some(3));
+        assert_eq!(plane.histogram(2).unwrap(), [0, 1, 1, 2]);
+        assert_eq!(
+            plane.histogram(1),
+            Err(Error::UnsupportedBitDepth { bit_depth: 1 })
+        );
+        assert_eq!(
+            plane.histogram(0),
+            Err(Error::UnsupportedBitDepth { bit_depth: 0 })
+        );
+        assert_eq!(
+            plane.histogram(40),
+            Err(Error::UnsupportedBitDepth { bit_depth: 40 })
+        );
+
+        let full = Plane::from_slice(&[u16::MAX; 64], 8);
+        assert_eq!(full.sum(), 64 * u64::from(u16::MAX));
+
+        let empty = Plane::<u8>::new(0, 0, 0, 0, 0, 0);
+        assert_eq!(empty.sum(), 0);
+        assert!(empty.mean().is_nan());
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.max(), None);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_from_aligned_box() {
+        let data: AlignedBox<u8> =
+            AVec::from_slice(PlaneData::<u8>::DATA_ALIGNMENT, &[1, 2, 3, 4, 5, 6, 7, 8])
+                .into_boxed_slice();
+        let ptr = data.as_ptr();
+
+        let plane = Plane::from_aligned_box(
+            data,
+            PlaneConfig {
+                stride: 4,
+                alloc_height: 2,
+                width: 3,
+                height: 2,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 1,
+                yorigin: 0,
+            },
+        );
+
+        assert_eq!(plane.data.as_ptr(), ptr);
+        assert_eq!(
+            &[2, 3, 4, 6, 7, 8][..],
+            &plane.iter().collect::<Vec<_>>()[..]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_visible_pixel() {
+        #[rustfmt::skip]
+        let plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                1, 2, 3, 4, 0, 0,
+                5, 6, 7, 8, 0, 0,
+                0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 6,
+                alloc_height: 3,
+                width: 4,
+                height: 2,
+                xdec: 0,
+                ydec: 0,
+                xpad: 2,
+                ypad: 1,
+                xorigin: 0,
+                yorigin: 0,
+            },
+        };
+
+        assert_eq!(plane.visible_pixel(3, 1), Some(8));
+        assert_eq!(plane.p(4, 0), 0);
+        assert_eq!(plane.visible_pixel(4, 0), None);
+        assert_eq!(plane.p(0, 2), 0);
+        assert_eq!(plane.visible_pixel(0, 2), None);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_plane_arithmetic() {
+        let mut plane = Plane::from_slice(&[0u16, 500, 1000, 1023], 2);
+        let other = Plane::from_slice(&[10u16, 600, 20, 1023], 2);
+
+        plane.saturating_add_plane(&other, 10).unwrap();
+        assert_eq!(&[10, 1023, 1020, 1023][..], &plane.data[..]);
+
+        plane.saturating_sub_plane(&other).unwrap();
+        assert_eq!(&[0, 423, 1000, 0][..], &plane.data[..]);
+
+        let mut wide = Plane::from_slice(&[65000u16, 1], 2);
+        wide.saturating_add_plane(&Plane::from_slice(&[1000, 1], 2), 40)
+            .unwrap();
+        assert_eq!(&[65535, 2][..], &wide.data[..]);
+
+        let mut narrow = Plane::from_slice(&[200u8, 10], 2);
+        narrow
+            .saturating_add_plane(&Plane::from_slice(&[200, 10], 2), 10)
+            .unwrap();
+        assert_eq!(&[255, 20][..], &narrow.data[..]);
+
+        plane.blend(&other, 0.25).unwrap();
+        // 0.75 * 423 + 0.25 * 600 = 467.25, 0.75 * 1000 + 0.25 * 20 = 755
+        assert_eq!(&[3, 467, 755, 256][..], &plane.data[..]);
+
+        let mut plane = Plane::from_slice(&[0u8, 255], 2);
+        plane.blend(&Plane::from_slice(&[255, 0], 2), 1.0).unwrap();
+        assert_eq!(&[255, 0][..], &plane.data[..]);
+
+        plane.blend(&Plane::from_slice(&[0, 255], 2), 2.0).unwrap();
+        assert_eq!(&[0, 255][..], &plane.data[..]);
+        plane.blend(&Plane::from_slice(&[255, 0], 2), -1.0).unwrap();
+        assert_eq!(&[0, 255][..], &plane.data[..]);
+
+        let mut plane = Plane::from_slice(&[0u16; 4], 2);
+        let wrong = Plane::from_slice(&[0u16; 4], 4);
+        assert!(plane.saturating_add_plane(&wrong, 10).is_err());
+        assert!(plane.saturating_sub_plane(&wrong).is_err());
+        assert!(plane.blend(&wrong, 0.5).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_plane_resize() {
+        let plane = Plane::from_slice(&[0u8, 100, 200, 250], 2);
+
+        let nearest = plane.resize(4, 2, ResizeFilter::Nearest, 8);
+        assert_eq!(
+            &[0, 0, 100, 100, 200, 200, 250, 250][..],
+            &nearest.iter().collect::<Vec<_>>()[..]
+        );
+
+        let bilinear = plane.resize(4, 2, ResizeFilter::Bilinear, 8);
+        assert_eq!(
+            &[0, 25, 75, 100, 200, 213, 238, 250][..],
+            &bilinear.iter().collect::<Vec<_>>()[..]
+        );
+
+        let flat = Plane::from_slice(&[77u16; 64], 8);
+        for filter in [
+            ResizeFilter::Nearest,
+            ResizeFilter::Bilinear,
+            ResizeFilter::Lanczos3,
+        ] {
+            for (width, height) in [(3, 5), (8, 8), (13, 11)] {
+                let resized = flat.resize(width, height, filter, 10);
+                assert_eq!((resized.cfg.width, resized.cfg.height), (width, height));
+                assert!(resized.iter().all(|p| p == 77));
+            }
+        }
+
+        // Lanczos overshoots on sharp edges, which must be clamped
+        let edge = Plane::from_slice(&[0u16, 0, 0, 1023, 1023, 1023], 6);
+        let resized = edge.resize(17, 1, ResizeFilter::Lanczos3, 10);
+        assert_eq!(resized.min(), Some(0));
+        assert_eq!(resized.max(), Some(1023));
+
+        let edge = Plane::from_slice(&[0u8, 0, 0, 255, 255, 255], 6);
+        for bit_depth in [10, 40] {
+            let resized = edge.resize(17, 1, ResizeFilter::Lanczos3, bit_depth);
+            assert_eq!(resized.min(), Some(0));
+            assert_eq!(resized.max(), Some(255));
+            assert!((9..17).all(|x| resized.p(x, 0) > 128));
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_index() {
+        #[rustfmt::skip]
+        let mut plane = Plane::<u8> {
+            data: PlaneData::from_slice(&[
+                0, 0, 0, 0, 0, 0,
+                0, 1, 2, 3, 0, 0,
+                0, 4, 5, 6, 0, 0,
+                0, 0, 0, 0, 0, 0,
+            ]),
+            cfg: PlaneConfig {
+                stride: 6,
+                alloc_height: 4,
+                width: 3,
+                height: 2,
+                xdec: 0,
+                ydec: 0,
+                xpad: 0,
+                ypad: 0,
+                xorigin: 1,
+                yorigin: 1,
+            },
+        };
+
+        assert_eq!(&plane[1], &[4, 5, 6]);
+        assert_eq!(plane[(2, 0)], 3);
+
+        plane[(0, 1)] = 9;
+        plane[0].fill(7);
+        assert_eq!(
+            &[7, 7, 7, 9, 5, 6][..],
+            &plane.iter().collect::<Vec<_>>()[..]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    #[should_panic]
+    fn test_plane_index_padding() {
+        let plane = Plane::<u8>::new(4, 4, 0, 0, 8, 8);
+        let _ = plane[(4, 0)];
+    }
 
-        let mut output = vec![42u8; 64];
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_hash() {
+        use std::collections::hash_map::DefaultHasher;
 
-        plane.copy_to_raw_u8(&mut output, 8, 1);
+        fn hash(plane: &Plane<u8>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            plane.hash(&mut hasher);
+            hasher.finish()
+        }
 
-        println!("{:?}", &plane.data[..10]);
+        let mut padded = Plane::<u8>::new(4, 2, 0, 0, 8, 8);
+        padded.fill_with(|x, y| (x + y * 4) as u8);
+        let unpadded = Plane::from_slice(&[0, 1, 2, 3, 4, 5, 6, 7], 4);
+        assert_eq!(hash(&padded), hash(&unpadded));
 
-        assert_eq!(&output[..64], &plane.data[..64]);
+        padded[(3, 1)] = 0;
+        assert_ne!(hash(&padded), hash(&unpadded));
+
+        let reshaped = Plane::from_slice(&[0, 1, 2, 3, 4, 5, 6, 7], 2);
+        assert_ne!(hash(&reshaped), hash(&unpadded));
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_plane_downsample() {
-        #[rustfmt::skip]
-        let plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 1, 2, 3, 4, 0, 0,
-                0, 0, 8, 7, 6, 5, 0, 0,
-                0, 0, 9, 8, 7, 6, 0, 0,
-                0, 0, 2, 3, 4, 5, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-            ]),
-            cfg: PlaneConfig {
-                stride: 8,
-                alloc_height: 9,
-                width: 4,
-                height: 4,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 2,
-                yorigin: 3,
-            },
+    fn test_plane_map_in_place() {
+        let lut: Vec<u8> = (0..=255u8).map(|v| 255 - v).collect();
+
+        let mut plane = Plane::<u8>::new(4, 2, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (x + y * 4) as u8);
+        plane.map_in_place(|p| lut[p as usize]);
+        assert_eq!(
+            &[255, 254, 253, 252, 251, 250, 249, 248][..],
+            &plane.iter().collect::<Vec<_>>()[..]
+        );
+        // The padding keeps its initial value
+        assert_eq!(plane.data[0], 128);
+
+        plane.map_indexed_in_place(|x, y, p| if x == y { 0 } else { p });
+        assert_eq!(
+            &[0, 254, 253, 252, 251, 0, 249, 248][..],
+            &plane.iter().collect::<Vec<_>>()[..]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downscale_u16_max() {
+        let plane = Plane::from_slice(&vec![u16::MAX; 64 * 64], 64);
+
+        let downscaled = plane.downscale::<4>();
+        assert_eq!((downscaled.cfg.width, downscaled.cfg.height), (16, 16));
+        assert!(downscaled.iter().all(|p| p == u16::MAX));
+
+        let downscaled = plane.downscale::<64>();
+        assert_eq!(downscaled.iter().collect::<Vec<_>>(), [u16::MAX]);
+
+        // 257 * 257 * 65535 does not fit in u32
+        let plane = Plane::from_slice(&vec![u16::MAX; 257 * 257], 257);
+        let downscaled = plane.downscale::<257>();
+        assert_eq!(downscaled.iter().collect::<Vec<_>>(), [u16::MAX]);
+
+        let plane = Plane::from_slice(&[u8::MAX; 32 * 32], 32);
+        assert!(plane.downscale::<16>().iter().all(|p| p == u8::MAX));
+        assert!(plane.downscale::<32>().iter().all(|p| p == u8::MAX));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_enumerate_pixels() {
+        let mut plane = Plane::<u8>::new(3, 2, 0, 0, 4, 4);
+        for (x, y, pixel) in plane.enumerate_pixels_mut() {
+            *pixel = (x * 10 + y) as u8;
+        }
+
+        assert_eq!(
+            plane.enumerate_pixels().collect::<Vec<_>>(),
+            [
+                (0, 0, 0),
+                (1, 0, 10),
+                (2, 0, 20),
+                (0, 1, 1),
+                (1, 1, 11),
+                (2, 1, 21)
+            ]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_row_bytes() {
+        let mut plane = Plane::<u16>::new(2, 2, 0, 0, 4, 4);
+        plane.fill_with(|x, y| (0x0102 * (x + 1) + 0x1000 * y) as u16);
+
+        assert_eq!(plane.row_bytes(0), Some(vec![0x02, 0x01, 0x04, 0x02]));
+        assert_eq!(plane.row_bytes(1), Some(vec![0x02, 0x11, 0x04, 0x12]));
+        assert_eq!(plane.row_bytes(2), None);
+
+        let mut buf = vec![0xff; 16];
+        plane.row_bytes_into(1, &mut buf);
+        assert_eq!(buf, [0x02, 0x11, 0x04, 0x12]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_fields() {
+        let mut plane = Plane::<u8>::new(2, 4, 0, 0, 4, 4);
+        plane.fill_with(|x, y| (y * 10 + x) as u8);
+
+        let (top, bottom) = plane.separate_fields();
+        assert_eq!(
+            top.rows_iter().flatten().copied().collect::<Vec<_>>(),
+            [0, 1, 20, 21]
+        );
+        assert_eq!(
+            bottom.rows_iter().flatten().copied().collect::<Vec<_>>(),
+            [10, 11, 30, 31]
+        );
+
+        let woven = Plane::weave_fields(&top, &bottom).unwrap();
+        assert_eq!(woven.max_abs_diff(&plane), Some(0));
+
+        let other = Plane::new(2, 1, 0, 0, 0, 0);
+        assert!(Plane::weave_fields(&top, &other).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_copy_from() {
+        let mut src = Plane::<u16>::new(5, 3, 0, 0, 8, 8);
+        src.fill_with(|x, y| (y * 10 + x) as u16);
+        let mut dst = Plane::<u16>::new(5, 3, 0, 0, 0, 0);
+        assert_ne!(src.cfg.stride, dst.cfg.stride);
+
+        dst.copy_from(&src).unwrap();
+        assert_eq!(dst.max_abs_diff(&src), Some(0));
+
+        let mut other = Plane::<u16>::new(3, 5, 0, 0, 0, 0);
+        assert!(other.copy_from(&src).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_as_contiguous() {
+        let mut plane = Plane::<u8>::new(64, 2, 0, 0, 0, 2);
+        plane.fill_with(|x, y| (x + y) as u8);
+        let pixels = plane.as_contiguous().unwrap();
+        assert_eq!(pixels.len(), 128);
+        assert_eq!((pixels[0], pixels[63], pixels[64]), (0, 63, 1));
+
+        let padded = Plane::<u8>::new(64, 2, 0, 0, 8, 0);
+        assert!(padded.as_contiguous().is_none());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_config_validate() {
+        let cfg = PlaneConfig::new(100, 50, 1, 1, 8, 8, 2);
+        assert_eq!(cfg.validate(), Ok(()));
+
+        let mut bad = cfg.clone();
+        bad.stride = bad.width;
+        assert_eq!(bad.validate(), Err(Error::InvalidPlaneConfig));
+
+        let mut bad = cfg.clone();
+        bad.yorigin = 0;
+        assert_eq!(bad.validate(), Err(Error::InvalidPlaneConfig));
+
+        let mut bad = cfg;
+        bad.xdec = 2;
+        assert_eq!(bad.validate(), Err(Error::InvalidPlaneConfig));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn copy_from_raw_u8_u16_unaligned() {
+        let bytes = [0xff, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let mut plane = Plane::<u16>::new(2, 2, 0, 0, 0, 0);
+
+        plane.copy_from_raw_u8(&bytes[1..], 4, 2);
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [0x0201, 0x0403, 0x0605, 0x0807]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_abs_diff_into() {
+        let a = Plane::from_slice(&[10u16, 500, 1000, 0], 2);
+        let b = Plane::from_slice(&[12u16, 400, 1000, 1023], 2);
+        let mut dst = Plane::new(2, 2, 0, 0, 0, 0);
+
+        a.abs_diff_into(&b, &mut dst).unwrap();
+        assert_eq!(dst.iter().collect::<Vec<_>>(), [2, 100, 0, 1023]);
+
+        a.scaled_abs_diff_into(&b, &mut dst, 8, 10).unwrap();
+        assert_eq!(dst.iter().collect::<Vec<_>>(), [16, 800, 0, 1023]);
+
+        let mut wrong = Plane::new(1, 2, 0, 0, 0, 0);
+        assert!(a.abs_diff_into(&b, &mut wrong).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_copy_from_interleaved() {
+        let rgb = [1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let mut plane = Plane::<u8>::new(2, 2, 0, 0, 4, 4);
+        plane.copy_from_interleaved(&rgb, 3, 1, 1).unwrap();
+        assert_eq!(plane.iter().collect::<Vec<_>>(), [2, 5, 8, 11]);
+
+        let mut plane = Plane::<u16>::new(2, 1, 0, 0, 0, 0);
+        plane.copy_from_interleaved(&rgb[..8], 2, 1, 2).unwrap();
+        assert_eq!(plane.iter().collect::<Vec<_>>(), [0x0403, 0x0807]);
+
+        assert_eq!(
+            plane.copy_from_interleaved(&rgb, 2, 2, 2),
+            Err(Error::InvalidChannel {
+                channel: 2,
+                num_channels: 2
+            })
+        );
+        assert_eq!(
+            plane.copy_from_interleaved(&rgb, 2, 0, 2),
+            Err(Error::DataLength {
+                expected: 8,
+                found: 12
+            })
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_rescale_bit_depth() {
+        let mut plane = Plane::from_slice(&[0u16, 1, 2, 511, 512, 1023], 3);
+
+        plane.rescale_bit_depth(10, 12).unwrap();
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [0, 4, 8, 2044, 2048, 4092]
+        );
+        plane.rescale_bit_depth(12, 10).unwrap();
+        assert_eq!(plane.iter().collect::<Vec<_>>(), [0, 1, 2, 511, 512, 1023]);
+
+        plane.rescale_bit_depth(10, 8).unwrap();
+        assert_eq!(plane.iter().collect::<Vec<_>>(), [0, 0, 1, 128, 128, 255]);
+
+        let mut plane = Plane::from_slice(&[4094u16, 4095], 2);
+        plane.rescale_bit_depth(12, 10).unwrap();
+        assert_eq!(plane.iter().collect::<Vec<_>>(), [1023, 1023]);
+
+        for (from, to, bit_depth) in [(8, 40, 40), (40, 1, 40), (0, 8, 0), (16, 17, 17)] {
+            assert_eq!(
+                plane.rescale_bit_depth(from, to),
+                Err(Error::UnsupportedBitDepth { bit_depth })
+            );
+        }
+        let mut narrow = Plane::from_slice(&[200u8], 1);
+        assert_eq!(
+            narrow.rescale_bit_depth(8, 10),
+            Err(Error::UnsupportedBitDepth { bit_depth: 10 })
+        );
+        assert_eq!(plane.iter().collect::<Vec<_>>(), [1023, 1023]);
+        assert_eq!(narrow.p(0, 0), 200);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_try_as_slice() {
+        let plane = Plane::from_slice(&[1u8, 2, 3, 4], 2);
+        assert_eq!(plane.try_as_slice(), Ok(&[1, 2, 3, 4][..]));
+        assert_eq!(<&[u8]>::try_from(&plane), Ok(&[1, 2, 3, 4][..]));
+
+        let padded = Plane::<u8>::new(64, 2, 0, 0, 0, 2);
+        assert!(padded.as_contiguous().is_some());
+        assert_eq!(padded.try_as_slice(), Err(Error::Padded));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_packed_bits() {
+        let plane = Plane::from_slice(&[0x3ffu16, 0x001, 0x200, 0x155], 2);
+        let packed = plane.packed_bits(10);
+        assert_eq!(packed, [0xff, 0xc0, 0x18, 0x01, 0x55]);
+
+        let mut unpacked = Plane::new(2, 2, 0, 0, 4, 4);
+        unpacked.copy_from_packed_bits(&packed, 10).unwrap();
+        assert_eq!(unpacked.max_abs_diff(&plane), Some(0));
+
+        let plane = Plane::from_slice(&[0xabcu16, 0x123, 0x456], 3);
+        let packed = plane.packed_bits(12);
+        assert_eq!(packed, [0xab, 0xc1, 0x23, 0x45, 0x60]);
+
+        let mut unpacked = Plane::new(3, 1, 0, 0, 0, 0);
+        unpacked.copy_from_packed_bits(&packed, 12).unwrap();
+        assert_eq!(unpacked.max_abs_diff(&plane), Some(0));
+        assert_eq!(
+            unpacked.copy_from_packed_bits(&packed[1..], 12),
+            Err(Error::DataLength {
+                expected: 5,
+                found: 4
+            })
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_try_new() {
+        let width = NonZeroUsize::new(10).unwrap();
+        let height = NonZeroUsize::new(4).unwrap();
+
+        let plane = Plane::<u16>::try_new(width, height, 10).unwrap();
+        assert_eq!((plane.cfg.width, plane.cfg.height), (10, 4));
+        assert_eq!((plane.cfg.xpad, plane.cfg.ypad), (0, 0));
+
+        let plane = Plane::<u8>::try_new_padded(width, height, 8, 2, 8).unwrap();
+        assert_eq!((plane.cfg.xpad, plane.cfg.ypad), (8, 2));
+
+        assert_eq!(
+            Plane::<u8>::try_new(width, height, 10).err(),
+            Some(Error::UnsupportedBitDepth { bit_depth: 10 })
+        );
+        assert!(Plane::<u16>::try_new(width, height, 0).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_ext_traits() {
+        // Horizontal [1, 2, 1] blur, written against the traits only
+        fn blur<P: PlaneExt<u8>, Q: PlaneMutExt<u8>>(src: &P, dst: &mut Q) {
+            for y in 0..src.height() {
+                for x in 0..src.width() {
+                    let left = src.pixel(x.saturating_sub(1), y);
+                    let right = src.pixel((x + 1).min(src.width() - 1), y);
+                    let sum = u16::from(left) + 2 * u16::from(src.pixel(x, y)) + u16::from(right);
+                    *dst.pixel_mut(x, y) = ((sum + 2) / 4) as u8;
+                }
+            }
+        }
+
+        let src = Plane::from_slice(&[0u8, 0, 100, 0, 0, 0, 40, 40], 4);
+        let mut dst = Plane::new(4, 2, 0, 0, 4, 4);
+        blur(&src, &mut dst);
+
+        assert_eq!(dst.visible_row(0), [0, 25, 50, 25]);
+        assert_eq!(dst.visible_row(1), [0, 10, 30, 40]);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_translate() {
+        let new_plane = || {
+            let mut plane = Plane::<u8>::new(3, 3, 0, 0, 2, 2);
+            plane.fill_with(|x, y| (y * 3 + x + 1) as u8);
+            plane
         };
-        let downsampled = plane.downsampled(4, 4);
 
-        #[rustfmt::skip]
-        let expected = &[
-            5, 5,
-            6, 6,
-        ];
+        let mut plane = new_plane();
+        plane.translate(1, 1, 0);
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [0, 0, 0, 0, 1, 2, 0, 4, 5]
+        );
+
+        let mut plane = new_plane();
+        plane.translate(-2, -1, 0);
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [6, 0, 0, 9, 0, 0, 0, 0, 0]
+        );
+
+        let mut plane = new_plane();
+        plane.translate(0, 2, 0);
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [0, 0, 0, 0, 0, 0, 1, 2, 3]
+        );
+
+        let mut plane = new_plane();
+        plane.translate(5, 0, 7);
+        assert!(plane.iter().all(|p| p == 7));
+
+        let mut plane = new_plane();
+        plane.translate(0, -4, 7);
+        assert!(plane.iter().all(|p| p == 7));
+
+        let mut plane = new_plane();
+        plane.translate(0, 0, 7);
+        assert_eq!(plane, new_plane());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_rows_with_padding() {
+        let mut plane = Plane::<u8>::new(4, 2, 0, 0, 2, 1);
+        plane.fill(1);
+        plane.pad(4, 2);
+
+        let stride = plane.cfg.stride;
+        let rows: Vec<_> = plane.rows_with_padding().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|row| row.len() == stride));
+        // The padding is replicated from the visible pixels
+        assert_eq!(rows[0][plane.cfg.xorigin - 1], 1);
+
+        for row in plane.rows_with_padding_mut() {
+            row.fill(2);
+        }
+        assert!(plane.data.iter().all(|&p| p == 2));
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_byte_data() {
+        let mut plane = Plane::<u16>::new(2, 1, 0, 0, 4, 4);
+        plane.fill_with(|x, _| 0x0102 * (x as u16 + 1));
+
+        assert_eq!(
+            plane.byte_data().collect::<Vec<_>>(),
+            [0x02, 0x01, 0x04, 0x02]
+        );
+        assert_eq!(
+            plane
+                .byte_data_with_endianness(Endianness::Big)
+                .collect::<Vec<_>>(),
+            [0x01, 0x02, 0x02, 0x04]
+        );
+
+        let plane = Plane::from_slice(&[1u8, 2, 3], 3);
+        for endianness in [Endianness::Little, Endianness::Big] {
+            assert_eq!(
+                plane
+                    .byte_data_with_endianness(endianness)
+                    .collect::<Vec<_>>(),
+                [1, 2, 3]
+            );
+        }
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_fill_rect() {
+        let mut plane = Plane::<u8>::new(4, 3, 0, 0, 2, 2);
+        plane.fill(0);
+
+        plane.fill_rect(1, 1, 3, 2, 9).unwrap();
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [0, 0, 0, 0, 0, 9, 9, 9, 0, 9, 9, 9]
+        );
+
+        assert_eq!(
+            plane.fill_rect(2, 0, 3, 1, 1),
+            Err(Error::OutOfBounds {
+                x: 2,
+                y: 0,
+                width: 3,
+                height: 1,
+                plane_width: 4,
+                plane_height: 3,
+            })
+        );
+        assert!(plane.fill_rect(0, usize::MAX, 1, 2, 1).is_err());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_from_vec() {
+        let plane = Plane::from_vec(vec![1u16, 2, 3, 4, 5, 6], 3, 2).unwrap();
+        assert_eq!((plane.cfg.width, plane.cfg.height), (3, 2));
+        assert_eq!(plane.try_as_slice(), Ok(&[1, 2, 3, 4, 5, 6][..]));
+        assert_eq!(plane.p(0, 1), 4);
+
+        assert_eq!(
+            Plane::from_vec(vec![0u8; 5], 3, 2).err(),
+            Some(Error::DataLength {
+                expected: 6,
+                found: 5
+            })
+        );
+        assert!(Plane::from_vec(Vec::<u8>::new(), 0, 4).is_ok());
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_fill() {
+        // Contiguous visible pixels, with vertical padding only
+        let mut plane = Plane::<u8>::new(64, 2, 0, 0, 0, 1);
+        plane.fill(7);
+        assert!(plane.iter().all(|p| p == 7));
+        assert!(plane
+            .rows_with_padding()
+            .next()
+            .unwrap()
+            .iter()
+            .all(|&p| p == 128));
+        assert!(plane
+            .rows_with_padding()
+            .last()
+            .unwrap()
+            .iter()
+            .all(|&p| p == 128));
+
+        let mut plane = Plane::<u16>::new(3, 2, 0, 0, 2, 2);
+        plane.fill(1023);
+        assert!(plane.iter().all(|p| p == 1023));
+        assert_eq!(plane.data.iter().filter(|&&p| p == 1023).count(), 6);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_downsample_2x() {
+        let mut plane = Plane::<u16>::new(7, 5, 0, 0, 4, 4);
+        plane.fill_with(|x, y| ((x * 37 + y * 101) % 1024) as u16);
+
+        let downsampled = plane.downsample_2x();
+        assert_eq!((downsampled.cfg.width, downsampled.cfg.height), (3, 2));
+        assert_eq!((downsampled.cfg.xdec, downsampled.cfg.ydec), (1, 1));
+
+        for (x, y, pixel) in downsampled.enumerate_pixels() {
+            let sum: u32 = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .map(|&(dx, dy)| u32::from(plane.p(2 * x + dx, 2 * y + dy)))
+                .sum();
+            assert_eq!(u32::from(pixel), (sum + 2) / 4);
+        }
+
+        let plane = Plane::from_slice(&[0u8, 1, 1, 1], 2);
+        assert_eq!(plane.downsample_2x().p(0, 0), 1);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_indexed_rows_mut() {
+        let mut plane = Plane::<u8>::new(3, 4, 0, 0, 2, 2);
+
+        let rows = plane.indexed_rows_mut();
+        assert_eq!(rows.len(), 4);
+        for (y, row) in rows {
+            assert_eq!(row.len(), 3);
+            row.fill(y as u8);
+        }
+
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_iter_double_ended() {
+        let mut plane = Plane::<u8>::new(3, 3, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 3 + x) as u8);
+
+        let mut pixels = plane.iter();
+        assert_eq!(pixels.len(), 9);
+        assert_eq!(pixels.next_back(), Some(8));
+        assert_eq!(pixels.next(), Some(0));
+        assert_eq!(pixels.len(), 7);
+        assert_eq!(pixels.rev().collect::<Vec<_>>(), [7, 6, 5, 4, 3, 2, 1]);
+
+        let mut rows = plane.rows_iter();
+        assert_eq!(rows.next_back(), Some(&[6, 7, 8][..]));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows.next(), Some(&[0, 1, 2][..]));
+        assert_eq!(rows.next_back(), Some(&[3, 4, 5][..]));
+        assert_eq!(rows.next(), None);
+        assert_eq!(rows.next_back(), None);
+
+        assert_eq!(
+            plane.rows_range(0, 2).rev().collect::<Vec<_>>(),
+            [&[3, 4, 5], &[0, 1, 2]]
+        );
+
+        for (row, value) in plane.rows_iter_mut().rev().zip(0..) {
+            row.fill(value);
+        }
+        assert_eq!(
+            plane.iter().collect::<Vec<_>>(),
+            [2, 2, 2, 1, 1, 1, 0, 0, 0]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_to_vec() {
+        let pixels: Vec<u16> = (0..12).collect();
+        let plane = Plane::from_vec(pixels.clone(), 4, 3).unwrap();
+        assert_eq!(plane.to_vec(), pixels);
+
+        let mut padded = Plane::<u16>::new(4, 3, 0, 0, 2, 1);
+        padded.copy_from(&plane).unwrap();
+        padded.pad(4, 3);
+        assert_eq!(padded.to_vec(), pixels);
+        assert_eq!(padded.into_vec(), pixels);
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_column() {
+        let mut plane = Plane::<u8>::new(3, 4, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 3 + x) as u8);
+
+        assert_eq!(plane.column(1).unwrap().collect::<Vec<_>>(), [1, 4, 7, 10]);
+        assert!(plane.column(3).is_none());
+
+        for (y, pixel) in plane.column_mut(2).unwrap().enumerate() {
+            *pixel = 100 + y as u8;
+        }
+        assert!(plane.column_mut(3).is_none());
+        assert_eq!(plane.rows_iter().next_back().unwrap(), [9, 10, 103]);
+        assert_eq!(
+            plane.column(2).unwrap().collect::<Vec<_>>(),
+            [100, 101, 102, 103]
+        );
+    }
+
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_is_uniform() {
+        let mut plane = Plane::<u16>::new(5, 4, 0, 0, 2, 2);
+        plane.fill(300);
+        assert_eq!(plane.is_uniform(), Some(300));
+
+        // Padding is not compared
+        plane.data[0] = 0;
+        assert_eq!(plane.is_uniform(), Some(300));
+
+        plane[(4, 3)] = 301;
+        assert_eq!(plane.is_uniform(), None);
+        plane[(4, 3)] = 300;
+        plane[(2, 0)] = 301;
+        assert_eq!(plane.is_uniform(), None);
+
+        assert_eq!(Plane::<u8>::new(0, 0, 0, 0, 0, 0).is_uniform(), None);
+    }
 
-        let v: Vec<_> = downsampled.iter().collect();
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_clone_into() {
+        let mut src = Plane::<u8>::new(6, 4, 0, 0, 4, 2);
+        src.fill_with(|x, y| (y * 6 + x) as u8);
+        src.pad(6, 4);
 
-        assert_eq!(&expected[..], &v[..]);
+        let mut dst = Plane::<u8>::new(6, 4, 0, 0, 4, 2);
+        let ptr = dst.data.as_ptr();
+        src.clone_into(&mut dst).unwrap();
+        assert_eq!(dst, src);
+        assert_eq!(dst.data.as_ptr(), ptr);
+
+        assert_eq!(
+            src.clone_into(&mut Plane::new(6, 4, 0, 0, 0, 0)).err(),
+            Some(Error::InvalidPlaneConfig)
+        );
+        assert_eq!(
+            src.clone_into(&mut Plane::new(4, 6, 0, 0, 4, 2)).err(),
+            Some(Error::DimensionMismatch {
+                expected: (6, 4),
+                found: (4, 6)
+            })
+        );
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_plane_downsample_odd() {
-        #[rustfmt::skip]
-        let plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 1, 2, 3, 4, 0, 0,
-                0, 0, 8, 7, 6, 5, 0, 0,
-                0, 0, 9, 8, 7, 6, 0, 0,
-                0, 0, 2, 3, 4, 5, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-            ]),
-            cfg: PlaneConfig {
-                stride: 8,
-                alloc_height: 9,
-                width: 3,
-                height: 3,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 2,
-                yorigin: 3,
-            },
-        };
-        let downsampled = plane.downsampled(3, 3);
+    fn test_plane_replace_data() {
+        let mut plane = Plane::<u8>::new(4, 4, 0, 0, 2, 2);
+        plane.fill(7);
+        let len = plane.cfg.stride * plane.cfg.alloc_height;
 
-        #[rustfmt::skip]
-        let expected = &[
-            5, 5,
-            6, 6,
-        ];
+        let old = plane.replace_data(PlaneData::new(len)).unwrap();
+        assert_eq!(old.len(), len);
+        assert!(old.contains(&7));
+        assert_eq!(plane.is_uniform(), Some(128));
 
-        let v: Vec<_> = downsampled.iter().collect();
-        assert_eq!(&expected[..], &v[..]);
+        assert_eq!(
+            plane.replace_data(PlaneData::new(len - 1)).err(),
+            Some(Error::DataLength {
+                expected: len,
+                found: len - 1
+            })
+        );
+        assert_eq!(plane.is_uniform(), Some(128));
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_plane_downscale() {
-        #[rustfmt::skip]
-        let plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 1, 4, 5, 0, 0,
-                0, 0, 2, 3, 6, 7, 0, 0,
-                0, 0, 8, 9, 7, 5, 0, 0,
-                0, 0, 9, 8, 3, 1, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-            ]),
-            cfg: PlaneConfig {
-                stride: 8,
-                alloc_height: 9,
-                width: 4,
-                height: 4,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 2,
-                yorigin: 3,
-            },
-        };
-        let downscaled = plane.downscale::<2>();
+    fn test_plane_pad_constant() {
+        for (xpad, ypad) in [(0, 0), (2, 0), (0, 3), (4, 1)] {
+            let mut plane = Plane::<u16>::new(5, 3, 0, 0, xpad, ypad);
+            plane.fill(512);
+            plane.pad_constant(64);
+
+            assert_eq!(plane.is_uniform(), Some(512));
+            let border = plane.data.iter().filter(|&&p| p == 64).count();
+            assert_eq!(border, plane.data.len() - 5 * 3);
+        }
+    }
 
-        #[rustfmt::skip]
-        let expected = &[
-            2, 6,
-            9, 4
-        ];
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_with_padding() {
+        let mut plane = Plane::<u16>::new(4, 3, 1, 1, 0, 0);
+        plane.fill_with(|x, y| (y * 4 + x + 1) as u16);
+
+        let padded = plane.with_padding(2, 2, PaddingFill::Replicate);
+        assert_eq!((padded.cfg.xpad, padded.cfg.ypad), (2, 2));
+        assert_eq!((padded.cfg.xdec, padded.cfg.ydec), (1, 1));
+        assert_eq!(padded.to_vec(), plane.to_vec());
+        // Corners replicate the corner pixels
+        assert_eq!(padded.data[0], 1);
+        assert_eq!(padded.data[padded.data.len() - 1], 12);
+
+        let padded = plane.with_padding(2, 1, PaddingFill::Constant(0));
+        assert_eq!(padded.to_vec(), plane.to_vec());
+        assert_eq!(
+            padded.data.iter().filter(|&&p| p == 0).count(),
+            padded.data.len() - 12
+        );
 
-        let v: Vec<_> = downscaled.iter().collect();
-        assert_eq!(&expected[..], &v[..]);
+        let unpadded = padded.with_padding(0, 0, PaddingFill::Replicate);
+        assert_eq!(unpadded.cfg, plane.cfg);
+        assert_eq!(unpadded.to_vec(), plane.to_vec());
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_plane_downscale_odd() {
-        #[rustfmt::skip]
-        let plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                1, 2, 3, 4, 1, 2, 3, 4,
-                0, 0, 8, 7, 6, 5, 8, 7,
-                6, 5, 8, 7, 6, 5, 8, 7,
-                6, 5, 8, 7, 0, 0, 2, 3,
-                4, 5, 0, 0, 9, 8, 7, 6,
-                0, 0, 0, 0, 2, 3, 4, 5,
-                0, 0, 0, 0, 2, 3, 4, 5,
-            ]),
-            cfg: PlaneConfig {
-                stride: 8,
-                alloc_height: 7,
-                width: 8,
-                height: 7,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 0,
-                yorigin: 0,
-            },
-        };
+    fn test_plane_try_copy_from_raw_u8() {
+        let stride = |s| NonZeroUsize::new(s).unwrap();
+        let mut plane = Plane::<u16>::new(2, 2, 0, 0, 0, 0);
 
-        let downscaled = plane.downscale::<3>();
+        // Odd byte stride with one byte of slack per row
+        let source = [1, 0, 2, 0, 0xff, 3, 0, 4, 0, 0xff];
+        plane.try_copy_from_raw_u8(&source, stride(5)).unwrap();
+        assert_eq!(plane.to_vec(), [1, 2, 3, 4]);
 
-        #[rustfmt::skip]
-        let expected = &[
-            4, 5,
-            3, 3
-        ];
+        assert_eq!(
+            plane.try_copy_from_raw_u8(&source, stride(3)).err(),
+            Some(Error::InvalidStride {
+                stride: 3,
+                row_len: 4
+            })
+        );
+        assert_eq!(
+            plane.try_copy_from_raw_u8(&source[..9], stride(5)).err(),
+            Some(Error::DataLength {
+                expected: 10,
+                found: 9
+            })
+        );
+    }
 
-        let v: Vec<_> = downscaled.iter().collect();
-        assert_eq!(&expected[..], &v[..]);
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_fold_rows() {
+        let mut plane = Plane::<u16>::new(3, 3, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 3 + x) as u16);
+
+        let sums = plane.fold_rows(
+            || 0u64,
+            |acc, row| acc + row.iter().map(|&p| u64::from(p)).sum::<u64>(),
+        );
+        assert_eq!(sums, [3, 12, 21]);
+
+        let maxima = plane.fold_rows(|| 0, |acc, row| row.iter().fold(acc, |a, &p| a.max(p)));
+        assert_eq!(maxima, [2, 5, 8]);
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_plane_downscale_odd_2() {
-        #[rustfmt::skip]
-        let plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                9, 8, 3, 1, 0, 1, 4, 5, 0, 0,
-                0, 1, 4, 5, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 9, 0,
-                0, 2, 3, 6, 7, 0, 0, 0, 0, 0,
-                0, 0, 8, 9, 7, 5, 0, 0, 0, 0,
-                9, 8, 3, 1, 0, 1, 4, 5, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 2, 3, 6, 7, 0,
-                0, 0, 0, 0, 0, 0, 8, 9, 7, 5,
-                0, 0, 0, 0, 9, 8, 3, 1, 0, 0
-            ]),
-            cfg: PlaneConfig {
-                stride: 10,
-                alloc_height: 10,
-                width: 10,
-                height: 10,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 0,
-                yorigin: 0,
-            },
-        };
-        let downscaled = plane.downscale::<3>();
+    fn test_plane_rows_step() {
+        let step = |s| NonZeroUsize::new(s).unwrap();
+        let mut plane = Plane::<u8>::new(5, 5, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 5 + x) as u8);
 
-        #[rustfmt::skip]
-        let expected = &[
-            3, 1, 2,
-            4, 4, 1,
-            0, 0, 4,
-        ];
+        let rows: Vec<_> = plane.rows_step(step(2)).collect();
+        assert_eq!(
+            rows,
+            [
+                &[0, 1, 2, 3, 4],
+                &[10, 11, 12, 13, 14],
+                &[20, 21, 22, 23, 24]
+            ]
+        );
+        assert_eq!(plane.rows_step(step(1)).count(), 5);
+        assert_eq!(plane.rows_step(step(9)).count(), 1);
 
-        let v: Vec<_> = downscaled.iter().collect();
-        assert_eq!(&expected[..], &v[..]);
+        let pixels: Vec<_> = plane.pixels_step(step(2), step(3)).collect();
+        assert_eq!(pixels, [0, 3, 10, 13, 20, 23]);
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_plane_pad() {
-        #[rustfmt::skip]
-        let mut plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 1, 2, 3, 4, 0, 0,
-                0, 0, 8, 7, 6, 5, 0, 0,
-                0, 0, 9, 8, 7, 6, 0, 0,
-                0, 0, 2, 3, 4, 5, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-            ]),
-            cfg: PlaneConfig {
-                stride: 8,
-                alloc_height: 9,
-                width: 4,
-                height: 4,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 2,
-                yorigin: 3,
-            },
-        };
-        plane.pad(4, 4);
+    fn test_plane_convolve_3x3() {
+        let mut plane = Plane::<u16>::new(4, 3, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 4 + x) as u16 * 50);
+
+        let identity = [0, 0, 0, 0, 1, 0, 0, 0, 0];
+        let out = plane.convolve_3x3(&identity, 1, 0, 10);
+        assert_eq!(out.to_vec(), plane.to_vec());
+        assert_eq!((out.cfg.xpad, out.cfg.ypad), (0, 0));
+
+        // Box blur with rounding, replicating the edges
+        let out = plane.convolve_3x3(&[1; 9], 9, 4, 10);
+        assert_eq!(out[0], [83, 117, 167, 200]);
+        assert_eq!(out[1][1], 250);
+
+        // Horizontal gradient, offset to mid-gray and clamped to 10 bits
+        let out = plane.convolve_3x3(&[0, 0, 0, -1, 0, 1, 0, 0, 0], 1, 512, 10);
+        assert_eq!(out[0], [562, 612, 612, 562]);
+        let out = plane.convolve_3x3(&[0, 0, 0, 0, 2, 0, 0, 0, 0], 1, 0, 10);
+        assert_eq!(out[2], [800, 900, 1000, 1023]);
+    }
 
-        #[rustfmt::skip]
-        assert_eq!(&[
-            1, 1, 1, 2, 3, 4, 4, 4,
-            1, 1, 1, 2, 3, 4, 4, 4,
-            1, 1, 1, 2, 3, 4, 4, 4,
-            1, 1, 1, 2, 3, 4, 4, 4,
-            8, 8, 8, 7, 6, 5, 5, 5,
-            9, 9, 9, 8, 7, 6, 6, 6,
-            2, 2, 2, 3, 4, 5, 5, 5,
-            2, 2, 2, 3, 4, 5, 5, 5,
-            2, 2, 2, 3, 4, 5, 5, 5,
-        ], &plane.data[..]);
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_pixel_clamped() {
+        let mut plane = Plane::<u8>::new(3, 2, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 3 + x) as u8);
+        plane.pad_constant(255);
+
+        assert_eq!(plane.pixel_clamped(1, 1), 4);
+        assert_eq!(plane.pixel_clamped(-5, -1), 0);
+        assert_eq!(plane.pixel_clamped(3, 0), 2);
+        assert_eq!(plane.pixel_clamped(isize::MAX, isize::MAX), 5);
+        assert_eq!(plane.pixel_clamped(isize::MIN, 7), 3);
     }
 
     #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
     #[test]
-    fn test_pixel_iterator() {
-        #[rustfmt::skip]
-        let plane = Plane::<u8> {
-            data: PlaneData::from_slice(&[
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 1, 2, 3, 4, 0, 0,
-                0, 0, 8, 7, 6, 5, 0, 0,
-                0, 0, 9, 8, 7, 6, 0, 0,
-                0, 0, 2, 3, 4, 5, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0,
-            ]),
-            cfg: PlaneConfig {
-                stride: 8,
-                alloc_height: 9,
-                width: 4,
-                height: 4,
-                xdec: 0,
-                ydec: 0,
-                xpad: 0,
-                ypad: 0,
-                xorigin: 2,
-                yorigin: 3,
-            },
-        };
+    fn test_plane_as_bytes() {
+        let mut plane = Plane::from_vec(vec![0x0102u16, 0x0304, 0x0506, 0x0708], 2, 2).unwrap();
+        let expected: Vec<u8> = [0x0102u16, 0x0304, 0x0506, 0x0708]
+            .iter()
+            .flat_map(|p| p.to_ne_bytes())
+            .collect();
+        assert_eq!(plane.as_bytes().unwrap(), expected);
+
+        plane.as_bytes_mut().unwrap()[..2].copy_from_slice(&0x0a0bu16.to_ne_bytes());
+        assert_eq!(plane[0][0], 0x0a0b);
+
+        let mut padded = Plane::<u16>::new(2, 2, 0, 0, 2, 0);
+        assert!(padded.as_bytes().is_none());
+        assert!(padded.as_bytes_mut().is_none());
+    }
 
-        let pixels: Vec<u8> = plane.iter().collect();
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_median_3x3() {
+        // Salt and pepper noise on a flat plane is removed entirely
+        let mut plane = Plane::<u8>::new(6, 5, 0, 0, 2, 2);
+        plane.fill(100);
+        plane[(1, 1)] = 255;
+        plane[(4, 3)] = 0;
+        plane[(3, 0)] = 255;
+        assert_eq!(plane.median_3x3().is_uniform(), Some(100));
+
+        // Compare against sorting the clamped neighborhoods of noisy pixels
+        let mut state = 12345u32;
+        let mut plane = Plane::<u16>::new(7, 6, 1, 1, 4, 4);
+        plane.fill_with(|_, _| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 22) as u16
+        });
+        let median = plane.median_3x3();
+        assert_eq!((median.cfg.xdec, median.cfg.xpad), (1, 0));
+        for y in 0..6 {
+            for x in 0..7 {
+                let mut neighborhood: Vec<u16> = (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+                    .map(|(x, y)| plane.pixel_clamped(x, y))
+                    .collect();
+                neighborhood.sort_unstable();
+                assert_eq!(median.p(x as usize, y as usize), neighborhood[4]);
+            }
+        }
+    }
 
-        assert_eq!(
-            &[1, 2, 3, 4, 8, 7, 6, 5, 9, 8, 7, 6, 2, 3, 4, 5,][..],
-            &pixels[..]
-        );
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), wasm_bindgen_test)]
+    #[test]
+    fn test_plane_threshold() {
+        let mut plane = Plane::<u16>::new(3, 2, 0, 0, 2, 2);
+        plane.fill_with(|x, y| (y * 3 + x) as u16 * 200);
+
+        let mask = plane.threshold(600, 1023, 0);
+        assert_eq!(mask.to_vec(), [0, 0, 0, 1023, 1023, 1023]);
+        assert_eq!((mask.cfg.xpad, mask.cfg.ypad), (0, 0));
+
+        plane.threshold_in_place(500, 1, 2);
+        assert_eq!(plane.to_vec(), [2, 2, 2, 1, 1, 1]);
     }
 }